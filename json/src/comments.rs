@@ -0,0 +1,388 @@
+//! Comment-preserving parsing
+//!
+//! JSON has no comments, but configuration files often contain them anyway.
+//! This module parses a document that may contain `// line` and
+//! `/* block */` comments, keeping each comment in a `Comments` table
+//! alongside the parsed `Value`, and writes the two back out together so that
+//! the comments survive an edit.
+//!
+//! A comment is attached to whatever follows it: the next array element or
+//! object member, the closing bracket of the enclosing array or object, or
+//! the end of the document. Comments are written back on their own lines in
+//! the same places, so their text is kept but their exact layout is not. A
+//! comment attached to a value that has since been removed is dropped.
+//!
+//! ```rust
+//! use serde_json::comments;
+//!
+//! let input = "{\n  // The port to listen on.\n  \"port\": 80\n}";
+//! let (mut value, comments) = comments::from_str(input).unwrap();
+//! assert_eq!(comments.leading("/port"), ["// The port to listen on."]);
+//!
+//! value.pointer_set("/port", serde_json::Value::U64(8080));
+//! let output = comments::to_string_pretty(&value, &comments).unwrap();
+//! assert_eq!(output, "{\n  // The port to listen on.\n  \"port\": 8080\n}");
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+
+use error::Result;
+use pointer;
+use ser;
+use value::Value;
+
+/// The comments found in a document, keyed by the JSON Pointer of the value
+/// they are attached to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comments {
+    leading: BTreeMap<String, Vec<String>>,
+    trailing: BTreeMap<String, Vec<String>>,
+    end: Vec<String>,
+}
+
+impl Comments {
+    /// Creates an empty table.
+    pub fn new() -> Comments {
+        Comments::default()
+    }
+
+    /// Returns true if the table holds no comments.
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_empty() &&
+        self.end.is_empty()
+    }
+
+    /// Returns the comments written before the value at `pointer`, or before
+    /// its key if it is an object member, in document order. Each comment
+    /// includes its `//` or `/*` and `*/` delimiters.
+    pub fn leading(&self, pointer: &str) -> &[String] {
+        self.leading.get(pointer).map_or(&[], |c| &c[..])
+    }
+
+    /// Returns the comments written just before the closing bracket of the
+    /// array or object at `pointer`.
+    pub fn trailing(&self, pointer: &str) -> &[String] {
+        self.trailing.get(pointer).map_or(&[], |c| &c[..])
+    }
+
+    /// Returns the comments written after the top-level value.
+    pub fn end(&self) -> &[String] {
+        &self.end
+    }
+}
+
+enum Anchor {
+    Leading(String),
+    Trailing(String),
+}
+
+/// Parses a JSON document that may contain comments, returning the value and
+/// the comments found in it.
+///
+/// Apart from the comments the document must be valid JSON. Errors are
+/// reported at the same line and column as they are in the input.
+pub fn from_str(s: &str) -> Result<(Value, Comments)> {
+    let (stripped, found) = strip_comments(s);
+    let value = try!(::de::from_str(&stripped));
+
+    let mut walker = Walker {
+        text: &stripped,
+        pos: 0,
+        anchors: Vec::new(),
+    };
+    walker.skip_whitespace();
+    walker.anchors.push((walker.pos, Anchor::Leading(String::new())));
+    walker.value(String::new());
+
+    let mut comments = Comments::new();
+    let mut anchors = walker.anchors.into_iter().peekable();
+    for (end, comment) in found {
+        while anchors.peek().map_or(false, |&(pos, _)| pos < end) {
+            anchors.next();
+        }
+        match anchors.peek() {
+            Some(&(_, Anchor::Leading(ref path))) => {
+                comments.leading
+                    .entry(path.clone())
+                    .or_insert_with(Vec::new)
+                    .push(comment);
+            }
+            Some(&(_, Anchor::Trailing(ref path))) => {
+                comments.trailing
+                    .entry(path.clone())
+                    .or_insert_with(Vec::new)
+                    .push(comment);
+            }
+            None => comments.end.push(comment),
+        }
+    }
+    Ok((value, comments))
+}
+
+// Returns `s` with every comment overwritten by spaces, keeping line breaks
+// so that positions in it match positions in `s`, together with each comment
+// and the offset just past it. An unterminated block comment is left in place
+// for the parser to reject.
+fn strip_comments(s: &str) -> (String, Vec<(usize, String)>) {
+    let bytes = s.as_bytes();
+    let mut stripped = String::with_capacity(s.len());
+    let mut found = Vec::new();
+    let mut copied = 0;
+    let mut in_string = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        if in_string {
+            match bytes[i] {
+                b'\\' => i += 1,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+        let end = match (bytes[i], bytes.get(i + 1)) {
+            (b'"', _) => {
+                in_string = true;
+                None
+            }
+            (b'/', Some(&b'/')) => {
+                Some(s[i..].find('\n').map_or(s.len(), |len| i + len))
+            }
+            (b'/', Some(&b'*')) => s[i + 2..].find("*/").map(|len| i + len + 4),
+            _ => None,
+        };
+        match end {
+            Some(end) => {
+                let comment = s[i..end].trim_right_matches('\r');
+                stripped.push_str(&s[copied..i]);
+                stripped.extend(comment.bytes().map(|b| {
+                    if b == b'\n' { '\n' } else { ' ' }
+                }));
+                found.push((end, comment.to_owned()));
+                i += comment.len();
+                copied = i;
+            }
+            None => i += 1,
+        }
+    }
+    stripped.push_str(&s[copied..]);
+    (stripped, found)
+}
+
+// Walks a document already known to be valid JSON, recording where each
+// array element, object member and closing bracket begins.
+struct Walker<'a> {
+    text: &'a str,
+    pos: usize,
+    anchors: Vec<(usize, Anchor)>,
+}
+
+impl<'a> Walker<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\n') | Some(b'\t') | Some(b'\r') =
+                  self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_string(&mut self) {
+        self.pos += 1;
+        while let Some(b) = self.peek() {
+            self.pos += if b == b'\\' { 2 } else { 1 };
+            if b == b'"' {
+                return;
+            }
+        }
+    }
+
+    fn value(&mut self, path: String) {
+        match self.peek() {
+            Some(b'[') => self.array(path),
+            Some(b'{') => self.object(path),
+            Some(b'"') => self.skip_string(),
+            _ => {
+                while let Some(b) = self.peek() {
+                    match b {
+                        b',' | b']' | b'}' | b' ' | b'\n' | b'\t' | b'\r' => {
+                            return
+                        }
+                        _ => self.pos += 1,
+                    }
+                }
+            }
+        }
+    }
+
+    fn array(&mut self, path: String) {
+        self.pos += 1;
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b']') => {
+                    self.anchors.push((self.pos, Anchor::Trailing(path)));
+                    self.pos += 1;
+                    return;
+                }
+                Some(b',') => self.pos += 1,
+                Some(_) => {
+                    let child = pointer::join(&path, &index.to_string());
+                    let anchor = Anchor::Leading(child.clone());
+                    self.anchors.push((self.pos, anchor));
+                    self.value(child);
+                    index += 1;
+                }
+                None => return,
+            }
+        }
+    }
+
+    fn object(&mut self, path: String) {
+        self.pos += 1;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b'}') => {
+                    self.anchors.push((self.pos, Anchor::Trailing(path)));
+                    self.pos += 1;
+                    return;
+                }
+                Some(b',') => self.pos += 1,
+                Some(b'"') => {
+                    let start = self.pos;
+                    self.skip_string();
+                    let key = &self.text[start..self.pos];
+                    let key: String = match ::de::from_str(key) {
+                        Ok(key) => key,
+                        Err(_) => return,
+                    };
+                    let child = pointer::join(&path, &key);
+                    self.anchors.push((start, Anchor::Leading(child.clone())));
+                    self.skip_whitespace();
+                    self.pos += 1; // the ':'
+                    self.skip_whitespace();
+                    self.value(child);
+                }
+                _ => return,
+            }
+        }
+    }
+}
+
+/// Writes `value` as pretty-printed JSON with the comments in `comments`
+/// restored, each on its own line.
+pub fn to_writer_pretty<W>(
+    writer: &mut W,
+    value: &Value,
+    comments: &Comments
+) -> Result<()>
+    where W: io::Write,
+{
+    try!(write_comments(writer, comments.leading(""), 0));
+    try!(write_value(writer, value, "", comments, 0));
+    for comment in comments.end() {
+        try!(writer.write_all(b"\n"));
+        try!(writer.write_all(comment.as_bytes()));
+    }
+    Ok(())
+}
+
+/// Writes `value` as a pretty-printed JSON string with the comments in
+/// `comments` restored, each on its own line.
+pub fn to_string_pretty(value: &Value, comments: &Comments) -> Result<String> {
+    let mut writer = Vec::with_capacity(128);
+    try!(to_writer_pretty(&mut writer, value, comments));
+    let string = unsafe {
+        // We do not emit invalid UTF-8.
+        String::from_utf8_unchecked(writer)
+    };
+    Ok(string)
+}
+
+fn write_value<W>(
+    writer: &mut W,
+    value: &Value,
+    path: &str,
+    comments: &Comments,
+    indent: usize
+) -> Result<()>
+    where W: io::Write,
+{
+    let inner = indent + 1;
+    match *value {
+        Value::Array(ref list) => {
+            let trailing = comments.trailing(path);
+            if list.is_empty() && trailing.is_empty() {
+                return writer.write_all(b"[]").map_err(From::from);
+            }
+            try!(writer.write_all(b"["));
+            for (i, element) in list.iter().enumerate() {
+                let child = pointer::join(path, &i.to_string());
+                try!(writer.write_all(b"\n"));
+                try!(write_comments(writer, comments.leading(&child), inner));
+                try!(write_indent(writer, inner));
+                try!(write_value(writer, element, &child, comments, inner));
+                if i + 1 < list.len() {
+                    try!(writer.write_all(b","));
+                }
+            }
+            try!(writer.write_all(b"\n"));
+            try!(write_comments(writer, trailing, inner));
+            try!(write_indent(writer, indent));
+            writer.write_all(b"]").map_err(From::from)
+        }
+        Value::Object(ref map) => {
+            let trailing = comments.trailing(path);
+            if map.is_empty() && trailing.is_empty() {
+                return writer.write_all(b"{}").map_err(From::from);
+            }
+            try!(writer.write_all(b"{"));
+            for (i, (key, element)) in map.iter().enumerate() {
+                let child = pointer::join(path, key);
+                try!(writer.write_all(b"\n"));
+                try!(write_comments(writer, comments.leading(&child), inner));
+                try!(write_indent(writer, inner));
+                try!(ser::escape_str(writer, key));
+                try!(writer.write_all(b": "));
+                try!(write_value(writer, element, &child, comments, inner));
+                if i + 1 < map.len() {
+                    try!(writer.write_all(b","));
+                }
+            }
+            try!(writer.write_all(b"\n"));
+            try!(write_comments(writer, trailing, inner));
+            try!(write_indent(writer, indent));
+            writer.write_all(b"}").map_err(From::from)
+        }
+        _ => ser::to_writer(writer, value),
+    }
+}
+
+fn write_comments<W>(
+    writer: &mut W,
+    comments: &[String],
+    indent: usize
+) -> Result<()>
+    where W: io::Write,
+{
+    for comment in comments {
+        try!(write_indent(writer, indent));
+        try!(writer.write_all(comment.as_bytes()));
+        try!(writer.write_all(b"\n"));
+    }
+    Ok(())
+}
+
+fn write_indent<W>(writer: &mut W, indent: usize) -> Result<()>
+    where W: io::Write,
+{
+    for _ in 0..indent {
+        try!(writer.write_all(b"  "));
+    }
+    Ok(())
+}
@@ -2,8 +2,9 @@
 //!
 //! This module provides for JSON deserialization with the type `Deserializer`.
 
-use std::{i32, u64};
-use std::io;
+use std::{cmp, f64, i32, u64, usize};
+use std::io::{self, BufRead};
+use std::mem;
 use std::marker::PhantomData;
 
 use serde::de;
@@ -11,6 +12,7 @@ use serde::de;
 use super::error::{Error, ErrorCode, Result};
 
 use read::{self, Read};
+use value::{Map, Value};
 
 //////////////////////////////////////////////////////////////////////////////
 
@@ -91,10 +93,149 @@ impl<Iter> de::Deserializer for Deserializer<Iter>
 
 //////////////////////////////////////////////////////////////////////////////
 
+/// Options controlling which non-standard inputs the parser accepts.
+///
+/// Every option is off by default, so the default options parse exactly the
+/// JSON grammar. A configured `ParseOptions` can be kept and reused for any
+/// number of parses through its `from_str`, `from_slice` and `from_reader`
+/// methods.
+///
+/// ```rust
+/// use serde_json::Value;
+/// use serde_json::de::ParseOptions;
+///
+/// let options = ParseOptions::new()
+///     .allow_non_finite(true)
+///     .max_elements(16);
+/// let value: Value = options.from_str("[1, NaN]").unwrap();
+/// assert!(value.is_array());
+/// let value: Value = options.from_slice(b"Infinity").unwrap();
+/// assert!(value.is_f64());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    allow_non_finite: bool,
+    allow_loose_numbers: bool,
+    max_string_len: Option<usize>,
+    max_elements: Option<usize>,
+    max_depth: Option<usize>,
+}
+
+impl ParseOptions {
+    /// Creates the default, strict, set of options.
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// Accept the `NaN`, `Infinity` and `-Infinity` tokens emitted by some
+    /// JavaScript and Python encoders, parsing them into `f64` values.
+    pub fn allow_non_finite(mut self, allow: bool) -> Self {
+        self.allow_non_finite = allow;
+        self
+    }
+
+    /// Accept numbers with a leading `+`, such as `+5`, or with nothing
+    /// before or after the decimal point, such as `.5` and `5.`. They are
+    /// parsed as if written `5`, `0.5` and `5.0`.
+    pub fn allow_loose_numbers(mut self, allow: bool) -> Self {
+        self.allow_loose_numbers = allow;
+        self
+    }
+
+    /// Fail with `ErrorCode::StringTooLong` on any string or object key that
+    /// is longer than `max` bytes once unescaped. The check is made while the
+    /// string is read, so an oversized string is never fully buffered.
+    pub fn max_string_len(mut self, max: usize) -> Self {
+        self.max_string_len = Some(max);
+        self
+    }
+
+    /// Fail with `ErrorCode::TooManyElements` once the document has more than
+    /// `max` array elements and object members in total, counted across every
+    /// array and object rather than per container.
+    pub fn max_elements(mut self, max: usize) -> Self {
+        self.max_elements = Some(max);
+        self
+    }
+
+    /// Fail with a "recursion limit exceeded" error on arrays and objects
+    /// nested more than `max` levels deep. The default is 127.
+    ///
+    /// Deserializing with `from_str` and the like recurses once per level,
+    /// so a high limit there risks overflowing the stack. The `value_from_*`
+    /// methods do not recurse and can safely be given any limit, up to
+    /// `usize::MAX` for none at all.
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = Some(max);
+        self
+    }
+
+    /// Decodes a json value from a `&str` using these options.
+    pub fn from_str<T>(&self, s: &str) -> Result<T>
+        where T: de::Deserialize,
+    {
+        from_trait(read::StrRead::new(s), self.clone())
+    }
+
+    /// Decodes a json value from a byte slice `&[u8]` using these options.
+    pub fn from_slice<T>(&self, v: &[u8]) -> Result<T>
+        where T: de::Deserialize,
+    {
+        from_trait(read::SliceRead::new(v), self.clone())
+    }
+
+    /// Decodes a json value from a `std::io::Read` using these options.
+    pub fn from_reader<R, T>(&self, rdr: R) -> Result<T>
+        where R: io::Read,
+              T: de::Deserialize,
+    {
+        from_trait(read::IteratorRead::new(rdr.bytes()), self.clone())
+    }
+
+    /// Parses a `Value` from a `&str` using these options, keeping track of
+    /// open arrays and objects on the heap rather than by recursion.
+    ///
+    /// The result is the same as `from_str` would give, but nesting depth is
+    /// limited only by `max_depth` and memory instead of by the size of the
+    /// call stack. Note that dropping, comparing or serializing a `Value`
+    /// still recurses once per level.
+    ///
+    /// ```rust
+    /// use std::iter;
+    /// use serde_json::de::ParseOptions;
+    ///
+    /// let json: String = iter::repeat('[').take(1000)
+    ///     .chain(iter::repeat(']').take(1000))
+    ///     .collect();
+    /// let options = ParseOptions::new().max_depth(1000);
+    /// assert!(options.value_from_str(&json).is_ok());
+    /// assert!(ParseOptions::new().value_from_str(&json).is_err());
+    /// ```
+    pub fn value_from_str(&self, s: &str) -> Result<Value> {
+        value_from_trait(read::StrRead::new(s), self.clone())
+    }
+
+    /// Parses a `Value` from a byte slice `&[u8]` like `value_from_str`.
+    pub fn value_from_slice(&self, v: &[u8]) -> Result<Value> {
+        value_from_trait(read::SliceRead::new(v), self.clone())
+    }
+
+    /// Parses a `Value` from a `std::io::Read` like `value_from_str`.
+    pub fn value_from_reader<R>(&self, rdr: R) -> Result<Value>
+        where R: io::Read,
+    {
+        value_from_trait(read::IteratorRead::new(rdr.bytes()), self.clone())
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
 struct DeserializerImpl<R: Read> {
     read: R,
     str_buf: Vec<u8>,
-    remaining_depth: u8,
+    remaining_depth: usize,
+    remaining_elements: usize,
+    options: ParseOptions,
 }
 
 macro_rules! overflow {
@@ -105,10 +246,17 @@ macro_rules! overflow {
 
 impl<R: Read> DeserializerImpl<R> {
     fn new(read: R) -> Self {
+        DeserializerImpl::with_options(read, ParseOptions::default())
+    }
+
+    fn with_options(read: R, options: ParseOptions) -> Self {
         DeserializerImpl {
             read: read,
             str_buf: Vec::with_capacity(128),
-            remaining_depth: 128,
+            remaining_depth: options.max_depth
+                .map_or(128, |max| max.saturating_add(1)),
+            remaining_elements: options.max_elements.unwrap_or(usize::MAX),
+            options: options,
         }
     }
 
@@ -121,6 +269,204 @@ impl<R: Read> DeserializerImpl<R> {
         }
     }
 
+    // Skips a UTF-8 byte order mark, which some tools write at the start of a
+    // file. Only valid before the first value.
+    fn parse_bom(&mut self) -> Result<()> {
+        if try!(self.peek()) == Some(b'\xEF') {
+            self.eat_char();
+            if try!(self.next_char()) != Some(b'\xBB') ||
+               try!(self.next_char()) != Some(b'\xBF') {
+                return Err(self.error(ErrorCode::ExpectedSomeValue));
+            }
+        }
+        Ok(())
+    }
+
+    // Checks that the next value is valid JSON, then hands the visitor its
+    // text exactly as written instead of its contents.
+    fn parse_raw<V>(&mut self, mut visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        try!(self.parse_whitespace());
+        let mark = self.read.begin_raw_buffering();
+        let parsed: Result<de::impls::IgnoredAny> =
+            de::Deserialize::deserialize(self);
+        let raw = self.read.end_raw_buffering(mark);
+        try!(parsed);
+        match String::from_utf8(raw) {
+            Ok(raw) => visitor.visit_string(raw),
+            Err(_) => Err(self.error(ErrorCode::InvalidUnicodeCodePoint)),
+        }
+    }
+
+    // Parses a `Value` like `de::Deserialize::deserialize` would, but keeps
+    // the arrays and objects still being filled in on an explicit stack
+    // instead of recursing into them. Scalars are parsed as usual.
+    fn parse_value_iterative(&mut self) -> Result<Value> {
+        enum Partial {
+            Array(Vec<Value>),
+            Object(Map<String, Value>, String),
+        }
+
+        let mut stack = Vec::new();
+        loop {
+            try!(self.parse_whitespace());
+            let mut value = match try!(self.peek_or_null()) {
+                b'[' => {
+                    self.remaining_depth -= 1;
+                    if self.remaining_depth == 0 {
+                        return Err(self.peek_error(stack_overflow()));
+                    }
+                    self.eat_char();
+                    try!(self.parse_whitespace());
+                    match try!(self.peek()) {
+                        Some(b']') => {
+                            self.eat_char();
+                            self.remaining_depth += 1;
+                            Value::Array(Vec::new())
+                        }
+                        Some(_) => {
+                            try!(self.count_element());
+                            stack.push(Partial::Array(Vec::new()));
+                            continue;
+                        }
+                        None => {
+                            return Err(self
+                                .peek_error(ErrorCode::EOFWhileParsingList));
+                        }
+                    }
+                }
+                b'{' => {
+                    self.remaining_depth -= 1;
+                    if self.remaining_depth == 0 {
+                        return Err(self.peek_error(stack_overflow()));
+                    }
+                    self.eat_char();
+                    try!(self.parse_whitespace());
+                    match try!(self.peek()) {
+                        Some(b'}') => {
+                            self.eat_char();
+                            self.remaining_depth += 1;
+                            Value::Object(Map::new())
+                        }
+                        Some(_) => {
+                            let key = try!(self.parse_object_key());
+                            stack.push(Partial::Object(Map::new(), key));
+                            continue;
+                        }
+                        None => {
+                            return Err(self
+                                .peek_error(ErrorCode::EOFWhileParsingObject));
+                        }
+                    }
+                }
+                _ => try!(de::Deserialize::deserialize(self)),
+            };
+
+            // Add the finished value to its container, then keep closing
+            // containers for as long as they end right after it.
+            loop {
+                let closed = match stack.last_mut() {
+                    None => return Ok(value),
+                    Some(&mut Partial::Array(ref mut vec)) => {
+                        vec.push(value);
+                        try!(self.parse_element_end())
+                    }
+                    Some(&mut Partial::Object(ref mut map, ref mut key)) => {
+                        map.insert(mem::replace(key, String::new()), value);
+                        match try!(self.parse_member_end()) {
+                            Some(next) => {
+                                *key = next;
+                                false
+                            }
+                            None => true,
+                        }
+                    }
+                };
+                if !closed {
+                    break;
+                }
+                self.remaining_depth += 1;
+                value = match stack.pop() {
+                    Some(Partial::Array(vec)) => Value::Array(vec),
+                    Some(Partial::Object(map, _)) => Value::Object(map),
+                    None => unreachable!(),
+                };
+            }
+        }
+    }
+
+    // Parses what follows an array element: a `,` before the next element,
+    // returning false, or the closing `]`, returning true.
+    fn parse_element_end(&mut self) -> Result<bool> {
+        try!(self.parse_whitespace());
+        match try!(self.peek()) {
+            Some(b',') => {
+                self.eat_char();
+                try!(self.count_element());
+                Ok(false)
+            }
+            Some(b']') => {
+                self.eat_char();
+                Ok(true)
+            }
+            Some(_) => Err(self.peek_error(ErrorCode::ExpectedListCommaOrEnd)),
+            None => Err(self.peek_error(ErrorCode::EOFWhileParsingList)),
+        }
+    }
+
+    // Parses what follows an object member: a `,` and the next key, which is
+    // returned, or the closing `}`, returning None.
+    fn parse_member_end(&mut self) -> Result<Option<String>> {
+        try!(self.parse_whitespace());
+        match try!(self.peek()) {
+            Some(b',') => {
+                self.eat_char();
+                try!(self.parse_whitespace());
+                self.parse_object_key().map(Some)
+            }
+            Some(b'}') => {
+                self.eat_char();
+                Ok(None)
+            }
+            Some(_) => {
+                Err(self.peek_error(ErrorCode::ExpectedObjectCommaOrEnd))
+            }
+            None => Err(self.peek_error(ErrorCode::EOFWhileParsingObject)),
+        }
+    }
+
+    // Parses an object key and the colon after it, counting the member.
+    fn parse_object_key(&mut self) -> Result<String> {
+        try!(self.count_element());
+        match try!(self.peek()) {
+            Some(b'"') => {
+                self.eat_char();
+                self.str_buf.clear();
+                let max_len = self.max_string_len();
+                let key = String::from(try!(self.read
+                    .parse_str(&mut self.str_buf, max_len)));
+                try!(self.parse_object_colon());
+                Ok(key)
+            }
+            Some(_) => Err(self.peek_error(ErrorCode::KeyMustBeAString)),
+            None => Err(self.peek_error(ErrorCode::EOFWhileParsingValue)),
+        }
+    }
+
+    /// Called before parsing each array element or object member.
+    fn count_element(&mut self) -> Result<()> {
+        if self.remaining_elements == 0 {
+            return Err(self.peek_error(ErrorCode::TooManyElements));
+        }
+        self.remaining_elements -= 1;
+        Ok(())
+    }
+
+    fn max_string_len(&self) -> usize {
+        self.options.max_string_len.unwrap_or(usize::MAX)
+    }
+
     fn eof(&mut self) -> Result<bool> {
         Ok(try!(self.peek()).is_none())
     }
@@ -195,15 +541,40 @@ impl<R: Read> DeserializerImpl<R> {
                 try!(self.parse_ident(b"alse"));
                 visitor.visit_bool(false)
             }
+            b'N' if self.options.allow_non_finite => {
+                self.eat_char();
+                try!(self.parse_ident(b"aN"));
+                visitor.visit_f64(f64::NAN)
+            }
+            b'I' if self.options.allow_non_finite => {
+                self.eat_char();
+                try!(self.parse_ident(b"nfinity"));
+                visitor.visit_f64(f64::INFINITY)
+            }
             b'-' => {
                 self.eat_char();
-                self.parse_integer(false, visitor)
+                if self.options.allow_non_finite &&
+                   try!(self.peek_or_null()) == b'I' {
+                    self.eat_char();
+                    try!(self.parse_ident(b"nfinity"));
+                    visitor.visit_f64(f64::NEG_INFINITY)
+                } else {
+                    self.parse_any_number(false, visitor)
+                }
+            }
+            b'+' if self.options.allow_loose_numbers => {
+                self.eat_char();
+                self.parse_any_number(true, visitor)
+            }
+            b'.' if self.options.allow_loose_numbers => {
+                self.parse_any_number(true, visitor)
             }
-            b'0'...b'9' => self.parse_integer(true, visitor),
+            b'0'...b'9' => self.parse_any_number(true, visitor),
             b'"' => {
                 self.eat_char();
                 self.str_buf.clear();
-                let s = try!(self.read.parse_str(&mut self.str_buf));
+                let max_len = self.max_string_len();
+                let s = try!(self.read.parse_str(&mut self.str_buf, max_len));
                 visitor.visit_str(s)
             }
             b'[' => {
@@ -258,6 +629,57 @@ impl<R: Read> DeserializerImpl<R> {
         Ok(())
     }
 
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn parse_any_number<V>(&mut self, pos: bool, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        self.parse_integer(pos, visitor)
+    }
+
+    // Like `parse_integer`, but keeps the text of the number so that one an
+    // `f64` cannot hold exactly can be handed to the visitor as written.
+    // Visitors other than `Value` get the nearest `f64` instead.
+    #[cfg(feature = "arbitrary_precision")]
+    fn parse_any_number<V>(
+        &mut self,
+        pos: bool,
+        mut visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        let mark = self.read.begin_raw_buffering();
+        let parsed = self.parse_integer(pos, ::number::ParsedVisitor);
+        let raw = self.read.end_raw_buffering(mark);
+
+        let (number, nearest) = match try!(parsed) {
+            ::number::Parsed::U64(n) => return visitor.visit_u64(n),
+            ::number::Parsed::I64(n) => return visitor.visit_i64(n),
+            ::number::Parsed::F64(f) => {
+                let mut number = String::with_capacity(raw.len() + 1);
+                if !pos {
+                    number.push('-');
+                }
+                number.extend(raw.iter().map(|&b| b as char));
+                if !::number::is_valid(&number) ||
+                   ::number::round_trips(&number, f) {
+                    return visitor.visit_f64(f);
+                }
+                (number, Some(f))
+            }
+            ::number::Parsed::Big(number) => (number, None),
+        };
+
+        match ::number::visit(&mut visitor, &number) {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                match nearest.or_else(|| number.parse().ok()) {
+                    Some(f) if f.is_finite() => visitor.visit_f64(f),
+                    _ => Err(self.error(ErrorCode::NumberOutOfRange)),
+                }
+            }
+        }
+    }
+
     fn parse_integer<V>(&mut self, pos: bool, visitor: V) -> Result<V::Value>
         where V: de::Visitor,
     {
@@ -284,6 +706,12 @@ impl<R: Read> DeserializerImpl<R> {
                             // number as a `u64` until we grow too large. At that point, switch to
                             // parsing the value as a `f64`.
                             if overflow!(res * 10 + digit, u64::MAX) {
+                                #[cfg(feature = "arbitrary_precision")]
+                                return self.parse_big_integer(pos,
+                                                              res,
+                                                              digit,
+                                                              visitor);
+                                #[cfg(not(feature = "arbitrary_precision"))]
                                 return self.parse_long_integer(pos,
                                                                res,
                                                                1, // res * 10^1
@@ -298,6 +726,14 @@ impl<R: Read> DeserializerImpl<R> {
                     }
                 }
             }
+            b'.' if self.options.allow_loose_numbers => {
+                // Unlike a trailing one, a leading decimal point must be
+                // followed by a digit.
+                match try!(self.peek_or_null()) {
+                    b'0'...b'9' => self.parse_fraction(pos, 0, 0, visitor),
+                    _ => Err(self.peek_error(ErrorCode::InvalidNumber)),
+                }
+            }
             _ => Err(self.error(ErrorCode::InvalidNumber)),
         }
     }
@@ -335,6 +771,53 @@ impl<R: Read> DeserializerImpl<R> {
         }
     }
 
+    // Like `parse_long_integer`, but keeps the digits so that an integer too
+    // large for `u64` can be handed to the visitor exactly as written. Falls
+    // back to `f64` for visitors other than `Value`, and for decimals and
+    // exponents, which `parse_any_number` then checks against their text.
+    #[cfg(feature = "arbitrary_precision")]
+    fn parse_big_integer<V>(
+        &mut self,
+        pos: bool,
+        significand: u64,
+        digit: u64,
+        mut visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        let mut number = String::new();
+        if !pos {
+            number.push('-');
+        }
+        number.push_str(&significand.to_string());
+        number.push((b'0' + digit as u8) as char);
+        let mut exponent = 1;
+
+        loop {
+            match try!(self.peek_or_null()) {
+                c @ b'0'...b'9' => {
+                    self.eat_char();
+                    number.push(c as char);
+                    exponent += 1;
+                }
+                b'.' => {
+                    return self.parse_decimal(pos, significand, exponent, visitor);
+                }
+                b'e' | b'E' => {
+                    return self.parse_exponent(pos, significand, exponent, visitor);
+                }
+                _ => break,
+            }
+        }
+
+        match ::number::visit(&mut visitor, &number) {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                self.visit_f64_from_parts(pos, significand, exponent, visitor)
+            }
+        }
+    }
+
     fn parse_number<V>(
         &mut self,
         pos: bool,
@@ -354,6 +837,14 @@ impl<R: Read> DeserializerImpl<R> {
 
                     // Convert into a float if we underflow.
                     if neg > 0 {
+                        #[cfg(feature = "arbitrary_precision")]
+                        {
+                            let number = format!("-{}", significand);
+                            if let Ok(value) = ::number::visit(&mut visitor,
+                                                               &number) {
+                                return Ok(value);
+                            }
+                        }
                         visitor.visit_f64(-(significand as f64))
                     } else {
                         visitor.visit_i64(neg)
@@ -366,14 +857,26 @@ impl<R: Read> DeserializerImpl<R> {
     fn parse_decimal<V>(
         &mut self,
         pos: bool,
-        mut significand: u64,
-        mut exponent: i32,
+        significand: u64,
+        exponent: i32,
         visitor: V
     ) -> Result<V::Value>
         where V: de::Visitor,
     {
         self.eat_char();
+        self.parse_fraction(pos, significand, exponent, visitor)
+    }
 
+    // The digits after the decimal point, which has already been consumed.
+    fn parse_fraction<V>(
+        &mut self,
+        pos: bool,
+        mut significand: u64,
+        mut exponent: i32,
+        visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
         let mut at_least_one_digit = false;
         while let c @ b'0'...b'9' = try!(self.peek_or_null()) {
             self.eat_char();
@@ -393,7 +896,7 @@ impl<R: Read> DeserializerImpl<R> {
             exponent -= 1;
         }
 
-        if !at_least_one_digit {
+        if !at_least_one_digit && !self.options.allow_loose_numbers {
             return Err(self.peek_error(ErrorCode::InvalidNumber));
         }
 
@@ -611,11 +1114,14 @@ impl<R: Read> de::Deserializer for DeserializerImpl<R> {
     #[inline]
     fn deserialize_newtype_struct<V>(
         &mut self,
-        _name: &str,
+        name: &str,
         mut visitor: V
     ) -> Result<V::Value>
         where V: de::Visitor,
     {
+        if name == ::RAW_TOKEN {
+            return self.parse_raw(visitor);
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -705,6 +1211,7 @@ impl<'a, R: Read + 'a> de::SeqVisitor for SeqVisitor<'a, R> {
             }
         }
 
+        try!(self.de.count_element());
         let value = try!(de::Deserialize::deserialize(self.de));
         Ok(Some(value))
     }
@@ -764,6 +1271,8 @@ impl<'a, R: Read + 'a> de::MapVisitor for MapVisitor<'a, R> {
             }
         }
 
+        try!(self.de.count_element());
+
         match try!(self.de.peek()) {
             Some(b'"') => Ok(Some(try!(de::Deserialize::deserialize(self.de)))),
             Some(_) => Err(self.de.peek_error(ErrorCode::KeyMustBeAString)),
@@ -983,11 +1492,374 @@ impl<T, Iter> Iterator for StreamDeserializer<T, Iter>
 
 //////////////////////////////////////////////////////////////////////////////
 
-fn from_trait<R, T>(read: R) -> Result<T>
+/// Iterator that parses newline-delimited JSON, one `Value` per line.
+///
+/// Blank lines are skipped. A line that fails to parse produces an error
+/// whose line number is the line's position in the whole stream, and
+/// iteration continues with the next line. An I/O error ends the iteration.
+pub struct NdjsonReader<R> {
+    reader: io::BufReader<R>,
+    line: Vec<u8>,
+    line_number: usize,
+    done: bool,
+}
+
+impl<R> NdjsonReader<R>
+    where R: io::Read,
+{
+    /// Returns an `Iterator` of values parsed from the lines of `reader`.
+    pub fn new(reader: R) -> NdjsonReader<R> {
+        NdjsonReader {
+            reader: io::BufReader::new(reader),
+            line: Vec::new(),
+            line_number: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R> Iterator for NdjsonReader<R>
+    where R: io::Read,
+{
+    type Item = Result<Value>;
+
+    fn next(&mut self) -> Option<Result<Value>> {
+        while !self.done {
+            self.line.clear();
+            match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => self.done = true,
+                Ok(_) => {
+                    self.line_number += 1;
+                    if self.line.iter().all(|&b| is_whitespace(b)) {
+                        continue;
+                    }
+                    // Leave the line terminator out so that error columns
+                    // refer to the line itself.
+                    let end = self.line
+                        .iter()
+                        .rposition(|&b| b != b'\n' && b != b'\r')
+                        .map_or(0, |i| i + 1);
+                    self.line.truncate(end);
+                    return Some(from_slice(&self.line).map_err(|err| {
+                        match err {
+                            Error::Syntax(code, _, col) => {
+                                Error::Syntax(code, self.line_number, col)
+                            }
+                            err => err,
+                        }
+                    }));
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(Error::Io(err)));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn is_whitespace(b: u8) -> bool {
+    match b {
+        b' ' | b'\n' | b'\t' | b'\r' => true,
+        _ => false,
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+/// A single parse event produced by an `EventReader`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The start of an object, `{`.
+    StartObject,
+
+    /// The end of an object, `}`.
+    EndObject,
+
+    /// The start of an array, `[`.
+    StartArray,
+
+    /// The end of an array, `]`.
+    EndArray,
+
+    /// An object key. The key's value follows as the next event.
+    Key(String),
+
+    /// A JSON null value.
+    Null,
+
+    /// A JSON Boolean.
+    Bool(bool),
+
+    /// A negative JSON integer.
+    I64(i64),
+
+    /// A non-negative JSON integer.
+    U64(u64),
+
+    /// A JSON floating point number.
+    F64(f64),
+
+    /// A JSON number that would lose precision as an `I64`, `U64` or `F64`,
+    /// as written.
+    #[cfg(feature = "arbitrary_precision")]
+    BigNumber(String),
+
+    /// A JSON string.
+    String(String),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Container {
+    Array,
+    Object,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum EventState {
+    Start,
+    Value,
+    FirstElement,
+    FirstKey,
+    Key,
+    AfterValue,
+    Done,
+}
+
+/// Iterator that pulls parse events out of a single JSON document without
+/// building it in memory.
+///
+/// Only the current nesting path is kept, so a huge top-level array can be
+/// processed one element at a time. The iterator stops after the first
+/// error. The limits in `ParseOptions` apply as they do when parsing a whole
+/// value.
+///
+/// ```rust
+/// use serde_json::de::{Event, EventReader};
+///
+/// let events: Vec<Event> = EventReader::from_reader(r#"{"a":[1]}"#.as_bytes())
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(events, vec![Event::StartObject,
+///                         Event::Key("a".to_owned()),
+///                         Event::StartArray,
+///                         Event::U64(1),
+///                         Event::EndArray,
+///                         Event::EndObject]);
+/// ```
+pub struct EventReader<Iter>
+    where Iter: Iterator<Item = io::Result<u8>>,
+{
+    de: DeserializerImpl<read::IteratorRead<Iter>>,
+    stack: Vec<Container>,
+    state: EventState,
+}
+
+impl<Iter> EventReader<Iter>
+    where Iter: Iterator<Item = io::Result<u8>>,
+{
+    /// Returns an `Iterator` of parse events from an iterator over
+    /// `Iterator<Item=io::Result<u8>>`.
+    pub fn new(iter: Iter) -> EventReader<Iter> {
+        EventReader::with_options(iter, &ParseOptions::default())
+    }
+
+    /// Returns an `Iterator` of parse events from an iterator over
+    /// `Iterator<Item=io::Result<u8>>`, parsed using the given options.
+    pub fn with_options(
+        iter: Iter,
+        options: &ParseOptions
+    ) -> EventReader<Iter> {
+        EventReader {
+            de: DeserializerImpl::with_options(read::IteratorRead::new(iter),
+                                               options.clone()),
+            stack: Vec::new(),
+            state: EventState::Start,
+        }
+    }
+
+    fn parse_event(&mut self) -> Result<Option<Event>> {
+        if self.state == EventState::Start {
+            try!(self.de.parse_bom());
+            self.state = EventState::Value;
+        }
+        loop {
+            try!(self.de.parse_whitespace());
+
+            match self.state {
+                EventState::Start | EventState::Done => return Ok(None),
+                EventState::Value => return self.parse_value().map(Some),
+                EventState::FirstElement => {
+                    if try!(self.de.peek()) == Some(b']') {
+                        self.de.eat_char();
+                        return Ok(Some(self.pop(Event::EndArray)));
+                    }
+                    try!(self.de.count_element());
+                    self.state = EventState::Value;
+                }
+                EventState::FirstKey | EventState::Key => {
+                    match try!(self.de.peek()) {
+                        Some(b'}') if self.state == EventState::FirstKey => {
+                            self.de.eat_char();
+                            return Ok(Some(self.pop(Event::EndObject)));
+                        }
+                        Some(b'"') => {
+                            try!(self.de.count_element());
+                            self.de.eat_char();
+                            self.de.str_buf.clear();
+                            let key = {
+                                let de = &mut self.de;
+                                let max_len = de.max_string_len();
+                                String::from(try!(de.read
+                                    .parse_str(&mut de.str_buf, max_len)))
+                            };
+                            try!(self.de.parse_object_colon());
+                            self.state = EventState::Value;
+                            return Ok(Some(Event::Key(key)));
+                        }
+                        Some(_) => {
+                            return Err(self.de
+                                .peek_error(ErrorCode::KeyMustBeAString));
+                        }
+                        None => {
+                            return Err(self.de
+                                .peek_error(ErrorCode::EOFWhileParsingObject));
+                        }
+                    }
+                }
+                EventState::AfterValue => {
+                    let container = match self.stack.last() {
+                        Some(&container) => container,
+                        None => {
+                            self.state = EventState::Done;
+                            try!(self.de.end());
+                            return Ok(None);
+                        }
+                    };
+                    match (container, try!(self.de.peek())) {
+                        (Container::Array, Some(b',')) => {
+                            self.de.eat_char();
+                            try!(self.de.count_element());
+                            self.state = EventState::Value;
+                        }
+                        (Container::Object, Some(b',')) => {
+                            self.de.eat_char();
+                            self.state = EventState::Key;
+                        }
+                        (Container::Array, Some(b']')) => {
+                            self.de.eat_char();
+                            return Ok(Some(self.pop(Event::EndArray)));
+                        }
+                        (Container::Object, Some(b'}')) => {
+                            self.de.eat_char();
+                            return Ok(Some(self.pop(Event::EndObject)));
+                        }
+                        (Container::Array, Some(_)) => {
+                            return Err(self.de
+                                .peek_error(ErrorCode::ExpectedListCommaOrEnd));
+                        }
+                        (Container::Object, Some(_)) => {
+                            return Err(self.de
+                                .peek_error(ErrorCode::ExpectedObjectCommaOrEnd));
+                        }
+                        (Container::Array, None) => {
+                            return Err(self.de
+                                .peek_error(ErrorCode::EOFWhileParsingList));
+                        }
+                        (Container::Object, None) => {
+                            return Err(self.de
+                                .peek_error(ErrorCode::EOFWhileParsingObject));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Event> {
+        match try!(self.de.peek()) {
+            Some(b'[') => {
+                try!(self.push(Container::Array));
+                self.state = EventState::FirstElement;
+                Ok(Event::StartArray)
+            }
+            Some(b'{') => {
+                try!(self.push(Container::Object));
+                self.state = EventState::FirstKey;
+                Ok(Event::StartObject)
+            }
+            _ => {
+                let value = try!(de::Deserialize::deserialize(&mut self.de));
+                let event = match value {
+                    Value::Null => Event::Null,
+                    Value::Bool(b) => Event::Bool(b),
+                    Value::I64(n) => Event::I64(n),
+                    Value::U64(n) => Event::U64(n),
+                    Value::F64(n) => Event::F64(n),
+                    #[cfg(feature = "arbitrary_precision")]
+                    Value::BigNumber(n) => Event::BigNumber(n),
+                    Value::String(s) => Event::String(s),
+                    Value::Array(_) | Value::Object(_) => unreachable!(),
+                };
+                self.state = EventState::AfterValue;
+                Ok(event)
+            }
+        }
+    }
+
+    fn push(&mut self, container: Container) -> Result<()> {
+        self.de.remaining_depth -= 1;
+        if self.de.remaining_depth == 0 {
+            return Err(self.de.peek_error(stack_overflow()));
+        }
+        self.de.eat_char();
+        self.stack.push(container);
+        Ok(())
+    }
+
+    fn pop(&mut self, event: Event) -> Event {
+        self.stack.pop();
+        self.de.remaining_depth += 1;
+        self.state = EventState::AfterValue;
+        event
+    }
+}
+
+impl<R> EventReader<io::Bytes<R>>
+    where R: io::Read,
+{
+    /// Returns an `Iterator` of parse events from a `std::io::Read`.
+    pub fn from_reader(rdr: R) -> EventReader<io::Bytes<R>> {
+        EventReader::new(rdr.bytes())
+    }
+}
+
+impl<Iter> Iterator for EventReader<Iter>
+    where Iter: Iterator<Item = io::Result<u8>>,
+{
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Result<Event>> {
+        match self.parse_event() {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => None,
+            Err(err) => {
+                self.state = EventState::Done;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+//////////////////////////////////////////////////////////////////////////////
+
+fn from_trait<R, T>(read: R, options: ParseOptions) -> Result<T>
     where R: Read,
           T: de::Deserialize,
 {
-    let mut de = DeserializerImpl::new(read);
+    let mut de = DeserializerImpl::with_options(read, options);
+    try!(de.parse_bom());
     let value = try!(de::Deserialize::deserialize(&mut de));
 
     // Make sure the whole stream has been consumed.
@@ -995,13 +1867,23 @@ fn from_trait<R, T>(read: R) -> Result<T>
     Ok(value)
 }
 
+fn value_from_trait<R>(read: R, options: ParseOptions) -> Result<Value>
+    where R: Read,
+{
+    let mut de = DeserializerImpl::with_options(read, options);
+    try!(de.parse_bom());
+    let value = try!(de.parse_value_iterative());
+    try!(de.end());
+    Ok(value)
+}
+
 /// Decodes a json value from an iterator over an iterator
 /// `Iterator<Item=io::Result<u8>>`.
 pub fn from_iter<I, T>(iter: I) -> Result<T>
     where I: Iterator<Item = io::Result<u8>>,
           T: de::Deserialize,
 {
-    from_trait(read::IteratorRead::new(iter))
+    from_trait(read::IteratorRead::new(iter), ParseOptions::default())
 }
 
 /// Decodes a json value from a `std::io::Read`.
@@ -1009,19 +1891,420 @@ pub fn from_reader<R, T>(rdr: R) -> Result<T>
     where R: io::Read,
           T: de::Deserialize,
 {
-    from_iter(rdr.bytes())
+    ParseOptions::default().from_reader(rdr)
+}
+
+/// Parses newline-delimited JSON from a `std::io::Read`, yielding one `Value`
+/// per non-blank line.
+///
+/// ```rust
+/// let input = "{\"a\": 1}\n\n[2]\n";
+/// let values: Vec<_> = serde_json::de::from_ndjson(input.as_bytes())
+///     .map(Result::unwrap)
+///     .collect();
+/// assert_eq!(values.len(), 2);
+/// ```
+pub fn from_ndjson<R>(rdr: R) -> NdjsonReader<R>
+    where R: io::Read,
+{
+    NdjsonReader::new(rdr)
+}
+
+/// Decodes a json value from a `std::io::Read` using the given options.
+pub fn from_reader_with_options<R, T>(
+    rdr: R,
+    options: &ParseOptions
+) -> Result<T>
+    where R: io::Read,
+          T: de::Deserialize,
+{
+    options.from_reader(rdr)
 }
 
 /// Decodes a json value from a byte slice `&[u8]`.
 pub fn from_slice<T>(v: &[u8]) -> Result<T>
     where T: de::Deserialize,
 {
-    from_trait(read::SliceRead::new(v))
+    ParseOptions::default().from_slice(v)
+}
+
+/// Decodes a json value from a byte slice `&[u8]` using the given options.
+pub fn from_slice_with_options<T>(
+    v: &[u8],
+    options: &ParseOptions
+) -> Result<T>
+    where T: de::Deserialize,
+{
+    options.from_slice(v)
 }
 
 /// Decodes a json value from a `&str`.
 pub fn from_str<T>(s: &str) -> Result<T>
     where T: de::Deserialize,
 {
-    from_trait(read::StrRead::new(s))
+    ParseOptions::default().from_str(s)
+}
+
+/// Decodes a json value from a `&str` using the given options.
+///
+/// ```rust
+/// use serde_json::de::{self, ParseOptions};
+///
+/// let options = ParseOptions::new().allow_non_finite(true);
+/// let value: Vec<f64> = de::from_str_with_options("[NaN, -Infinity]", &options).unwrap();
+/// assert!(value[0].is_nan());
+/// assert_eq!(value[1], std::f64::NEG_INFINITY);
+/// ```
+pub fn from_str_with_options<T>(s: &str, options: &ParseOptions) -> Result<T>
+    where T: de::Deserialize,
+{
+    options.from_str(s)
+}
+
+/// Parses a `&str` as a `Value`, carrying on past syntax errors to report as
+/// many of them as possible in one pass, for example to show them all in an
+/// editor.
+///
+/// Recovery is best-effort. An array element or object member that cannot be
+/// parsed is reported and skipped up to the next `,` or closing bracket, and
+/// a closing bracket of the wrong kind is reported and taken to close the
+/// current array or object. Each error carries the same code, line and column
+/// that `from_str` would report if it were the first error in the document,
+/// though an error that follows an earlier one may be reported differently
+/// than it would be on its own.
+///
+/// Returns the value with whatever could not be parsed left out, or `None` if
+/// no value could be found at all, together with the errors in document
+/// order. The document is valid JSON if and only if there are no errors.
+///
+/// ```rust
+/// let (value, errors) = serde_json::de::parse_all_errors("[1, tru, 3, ]");
+/// assert_eq!(value.unwrap().to_string(), "[1,3]");
+/// assert_eq!(errors.len(), 2);
+/// ```
+pub fn parse_all_errors(s: &str) -> (Option<Value>, Vec<Error>) {
+    let mut recovery = Recovery {
+        text: s,
+        pos: 0,
+        remaining_depth: 128,
+        errors: Vec::new(),
+    };
+    if s.starts_with('\u{feff}') {
+        recovery.pos = '\u{feff}'.len_utf8();
+    }
+    let value = recovery.value(ErrorCode::TrailingCharacters);
+    recovery.skip_whitespace();
+    if recovery.pos < s.len() {
+        let pos = recovery.pos;
+        recovery.error(ErrorCode::TrailingCharacters, pos);
+    }
+    (value, recovery.errors)
+}
+
+// The state of `parse_all_errors`. The structure of the document is scanned
+// here, while each scalar and key is handed to the strict parser on its own.
+struct Recovery<'a> {
+    text: &'a str,
+    pos: usize,
+    remaining_depth: usize,
+    errors: Vec<Error>,
+}
+
+impl<'a> Recovery<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\n') | Some(b'\t') | Some(b'\r') =
+                  self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    // The line and column just before the byte at `index`, counted the way
+    // the strict parser counts them.
+    fn position(&self, index: usize) -> (usize, usize) {
+        let before = &self.text.as_bytes()[..index];
+        let line = 1 + before.iter().filter(|&&b| b == b'\n').count();
+        let column = match before.iter().rposition(|&b| b == b'\n') {
+            Some(newline) => index - newline - 1,
+            None => index,
+        };
+        (line, column)
+    }
+
+    // Records an error caused by the byte at `index`, or by the end of the
+    // input. Only the first error at any one place is kept.
+    fn error(&mut self, code: ErrorCode, index: usize) {
+        let (line, column) = self.position(cmp::min(index + 1,
+                                                     self.text.len()));
+        self.push_error(Error::Syntax(code, line, column));
+    }
+
+    fn push_error(&mut self, err: Error) {
+        if let Some(&Error::Syntax(_, line, column)) = self.errors.last() {
+            if let Error::Syntax(_, l, c) = err {
+                if (line, column) == (l, c) {
+                    return;
+                }
+            }
+        }
+        self.errors.push(err);
+    }
+
+    // Parses a value, reporting anything left over straight after a scalar
+    // with `trailing`, the error the strict parser reports there.
+    fn value(&mut self, trailing: ErrorCode) -> Option<Value> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            None => {
+                self.error(ErrorCode::EOFWhileParsingValue, start);
+                None
+            }
+            Some(b'[') => self.array(),
+            Some(b'{') => self.object(),
+            Some(b']') | Some(b'}') | Some(b',') | Some(b':') => {
+                self.error(ErrorCode::ExpectedSomeValue, start);
+                None
+            }
+            Some(_) => self.token(trailing),
+        }
+    }
+
+    fn array(&mut self) -> Option<Value> {
+        if !self.enter() {
+            return None;
+        }
+        let mut vec = Vec::new();
+        self.pos += 1;
+        self.skip_whitespace();
+        let pos = self.pos;
+        match self.peek() {
+            Some(b']') => self.pos += 1,
+            None => self.error(ErrorCode::EOFWhileParsingList, pos),
+            Some(_) => loop {
+                match self.value(ErrorCode::ExpectedListCommaOrEnd) {
+                    Some(value) => vec.push(value),
+                    None => self.skip(),
+                }
+                if self.container_end(b']', b'}') {
+                    break;
+                }
+            },
+        }
+        self.remaining_depth += 1;
+        Some(Value::Array(vec))
+    }
+
+    fn object(&mut self) -> Option<Value> {
+        if !self.enter() {
+            return None;
+        }
+        let mut map = Map::new();
+        self.pos += 1;
+        self.skip_whitespace();
+        let pos = self.pos;
+        match self.peek() {
+            Some(b'}') => self.pos += 1,
+            None => self.error(ErrorCode::EOFWhileParsingObject, pos),
+            Some(_) => loop {
+                let member = match self.key() {
+                    Some(key) => {
+                        self.value(ErrorCode::ExpectedObjectCommaOrEnd)
+                            .map(|value| (key, value))
+                    }
+                    None => None,
+                };
+                match member {
+                    Some((key, value)) => {
+                        map.insert(key, value);
+                    }
+                    None => self.skip(),
+                }
+                if self.container_end(b'}', b']') {
+                    break;
+                }
+            },
+        }
+        self.remaining_depth += 1;
+        Some(Value::Object(map))
+    }
+
+    // Checks the nesting limit before an array or object, skipping it if the
+    // limit has been reached.
+    fn enter(&mut self) -> bool {
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            let pos = self.pos;
+            self.error(stack_overflow(), pos);
+            self.remaining_depth += 1;
+            self.skip();
+            return false;
+        }
+        true
+    }
+
+    // Parses what follows an element or member: a `,`, returning false, or
+    // the closing bracket, returning true. Anything else is reported and
+    // skipped.
+    fn container_end(&mut self, close: u8, other: u8) -> bool {
+        let (expected, eof) = if close == b']' {
+            (ErrorCode::ExpectedListCommaOrEnd, ErrorCode::EOFWhileParsingList)
+        } else {
+            (ErrorCode::ExpectedObjectCommaOrEnd,
+             ErrorCode::EOFWhileParsingObject)
+        };
+        loop {
+            self.skip_whitespace();
+            let pos = self.pos;
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    return false;
+                }
+                Some(b) if b == close => {
+                    self.pos += 1;
+                    return true;
+                }
+                Some(b) if b == other => {
+                    self.error(expected, pos);
+                    self.pos += 1;
+                    return true;
+                }
+                None => {
+                    self.error(eof, pos);
+                    return true;
+                }
+                Some(_) => {
+                    self.error(expected.clone(), pos);
+                    self.skip();
+                }
+            }
+        }
+    }
+
+    // Parses an object key and the colon after it.
+    fn key(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some(b'"') => {}
+            Some(_) => {
+                self.error(ErrorCode::KeyMustBeAString, start);
+                return None;
+            }
+            None => {
+                self.error(ErrorCode::EOFWhileParsingValue, start);
+                return None;
+            }
+        }
+        let key = self.token(ErrorCode::ExpectedColon);
+        if key.is_some() {
+            self.skip_whitespace();
+            if self.peek() == Some(b':') {
+                self.pos += 1;
+            } else {
+                let pos = self.pos;
+                self.error(ErrorCode::ExpectedColon, pos);
+                return None;
+            }
+        }
+        key
+    }
+
+    // Parses the string, number or literal starting here with the strict
+    // parser. A string ends at its closing quote or the end of the line, and
+    // anything else at the next whitespace or punctuation.
+    fn token<T>(&mut self, trailing: ErrorCode) -> Option<T>
+        where T: de::Deserialize,
+    {
+        let start = self.pos;
+        let end = if self.peek() == Some(b'"') {
+            self.string_end(start)
+        } else {
+            let bytes = self.text.as_bytes();
+            let mut end = start;
+            while end < bytes.len() {
+                match bytes[end] {
+                    b' ' | b'\n' | b'\t' | b'\r' | b',' | b':' | b'"' | b'[' |
+                    b']' | b'{' | b'}' => break,
+                    _ => end += 1,
+                }
+            }
+            end
+        };
+        self.pos = end;
+
+        // Whatever ends the token is passed along as whitespace, so that an
+        // error there is reported where the strict parser would report it.
+        let mut probe = String::with_capacity(end - start + 1);
+        probe.push_str(&self.text[start..end]);
+        match self.peek() {
+            Some(b) if b == b'\n' || b == b'\r' || b == b'\t' => {
+                probe.push(b as char)
+            }
+            Some(_) => probe.push(' '),
+            None => {}
+        }
+        match from_str(&probe) {
+            Ok(value) => Some(value),
+            Err(Error::Syntax(code, line, column)) => {
+                let code = if code == ErrorCode::TrailingCharacters {
+                    trailing
+                } else {
+                    code
+                };
+                let (start_line, start_column) = self.position(start);
+                let err = if line == 1 {
+                    Error::Syntax(code, start_line, start_column + column)
+                } else {
+                    Error::Syntax(code, start_line + line - 1, column)
+                };
+                self.push_error(err);
+                None
+            }
+            Err(err) => {
+                self.push_error(err);
+                None
+            }
+        }
+    }
+
+    // Returns the offset just past the string starting at `start`, or of the
+    // end of its line if it is not closed on it.
+    fn string_end(&self, start: usize) -> usize {
+        let bytes = self.text.as_bytes();
+        let mut i = start + 1;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => return i + 1,
+                b'\n' => return i,
+                _ => i += 1,
+            }
+        }
+        bytes.len()
+    }
+
+    // Skips to the next `,` or closing bracket that is not inside a nested
+    // array, object or string.
+    fn skip(&mut self) {
+        let bytes = self.text.as_bytes();
+        let mut depth = 0;
+        while self.pos < bytes.len() {
+            match bytes[self.pos] {
+                b'"' => {
+                    self.pos = self.string_end(self.pos);
+                    continue;
+                }
+                b',' | b']' | b'}' if depth == 0 => return,
+                b'[' | b'{' => depth += 1,
+                b']' | b'}' => depth -= 1,
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
 }
@@ -0,0 +1,59 @@
+//! Visiting everything nested in a `Value`, for the `**` step of
+//! `Value::pointer_all` and the `..` step of JSONPath.
+
+use std::collections::HashSet;
+
+use pointer;
+use value::Value;
+
+/// The location of a visited value: a JSON Pointer for `pointer_all`, or
+/// nothing at all for JSONPath, which only needs the values.
+pub trait Path: Clone {
+    fn key(&self, key: &str) -> Self;
+    fn index(&self, index: usize) -> Self;
+}
+
+impl Path for String {
+    fn key(&self, key: &str) -> String {
+        pointer::join(self, key)
+    }
+
+    fn index(&self, index: usize) -> String {
+        pointer::join(self, &index.to_string())
+    }
+}
+
+impl Path for () {
+    fn key(&self, _key: &str) {}
+
+    fn index(&self, _index: usize) {}
+}
+
+/// Appends `value`, found at `path`, and everything nested in it to `out`,
+/// in document order, skipping any subtree whose root is already in `seen`.
+pub fn collect<'a, P>(
+    value: &'a Value,
+    path: P,
+    seen: &mut HashSet<*const Value>,
+    out: &mut Vec<(P, &'a Value)>
+)
+    where P: Path,
+{
+    if !seen.insert(value as *const Value) {
+        return;
+    }
+    out.push((path.clone(), value));
+    match *value {
+        Value::Object(ref map) => {
+            for (key, child) in map.iter() {
+                collect(child, path.key(key), seen, out);
+            }
+        }
+        Value::Array(ref list) => {
+            for (i, child) in list.iter().enumerate() {
+                collect(child, path.index(i), seen, out);
+            }
+        }
+        _ => {}
+    }
+}
@@ -85,6 +85,17 @@ pub enum ErrorCode {
 
     /// Unexpected end of hex excape.
     UnexpectedEndOfHexEscape,
+
+    /// A string is longer than `ParseOptions::max_string_len` allows.
+    StringTooLong,
+
+    /// The document has more array elements and object members than
+    /// `ParseOptions::max_elements` allows.
+    TooManyElements,
+
+    /// A float is `NaN` or infinite, which JSON cannot represent unless
+    /// `SerializeOptions::emit_non_finite` is set.
+    NonFiniteFloat,
 }
 
 impl fmt::Display for ErrorCode {
@@ -136,6 +147,9 @@ impl fmt::Display for ErrorCode {
             ErrorCode::UnexpectedEndOfHexEscape => {
                 "unexpected end of hex escape".fmt(f)
             }
+            ErrorCode::StringTooLong => "string too long".fmt(f),
+            ErrorCode::TooManyElements => "too many elements".fmt(f),
+            ErrorCode::NonFiniteFloat => "NaN or infinite float".fmt(f),
         }
     }
 }
@@ -178,6 +192,21 @@ impl fmt::Display for Error {
     }
 }
 
+/// Two `Syntax` errors are equal if their codes and positions are. `io::Error`
+/// cannot be compared, so two `Io` errors are equal if their kinds are.
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        match (self, other) {
+            (&Error::Syntax(ref a, a_line, a_col),
+             &Error::Syntax(ref b, b_line, b_col)) => {
+                a == b && a_line == b_line && a_col == b_col
+            }
+            (&Error::Io(ref a), &Error::Io(ref b)) => a.kind() == b.kind(),
+            _ => false,
+        }
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(error: io::Error) -> Error {
         Error::Io(error)
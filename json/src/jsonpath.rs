@@ -0,0 +1,273 @@
+//! JSONPath queries
+//!
+//! This module evaluates a subset of
+//! [JSONPath](http://goessner.net/articles/JsonPath/) against a `Value`. A
+//! path starts with `$`, the root, followed by any number of these steps:
+//!
+//! * `.name` or `['name']`: the member `name` of an object
+//! * `[n]`: element `n` of an array, counting back from the end if negative
+//! * `[start:end]`: the elements of an array from `start` up to but not
+//!   including `end`; either bound may be omitted or negative
+//! * `.*` or `[*]`: every element of an array or value of an object
+//! * `..` before any of the above: apply the step to the current values and
+//!   to everything nested in them, at any depth, as in `$..name`
+//!
+//! Filter expressions such as `[?(@.price < 10)]`, script expressions,
+//! unions such as `[0,1]` and slice steps are not supported.
+
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+
+use descendants;
+use value::Value;
+
+/// This type represents the ways a JSONPath string can be malformed or fall
+/// outside the supported subset.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathError {
+    /// The path did not start with `$`.
+    MissingRoot,
+
+    /// The path is not valid JSONPath at the given byte offset.
+    InvalidSyntax(usize),
+
+    /// The path uses a JSONPath feature this module does not implement, such
+    /// as a filter, starting at the given byte offset.
+    Unsupported(usize),
+}
+
+impl error::Error for PathError {
+    fn description(&self) -> &str {
+        match *self {
+            PathError::MissingRoot => "path must start with `$`",
+            PathError::InvalidSyntax(_) => "invalid path syntax",
+            PathError::Unsupported(_) => "unsupported path expression",
+        }
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PathError::MissingRoot => "path must start with `$`".fmt(f),
+            PathError::InvalidSyntax(pos) => {
+                write!(f, "invalid path syntax at position {}", pos)
+            }
+            PathError::Unsupported(pos) => {
+                write!(f, "unsupported path expression at position {}", pos)
+            }
+        }
+    }
+}
+
+enum Selector {
+    Child(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Wildcard,
+}
+
+struct Step {
+    descendants: bool,
+    selector: Selector,
+}
+
+/// Returns every value in `value` matched by the JSONPath `path`, in
+/// document order.
+///
+/// A path that is well formed but matches nothing gives an empty vector.
+///
+/// ```rust
+/// # use serde_json::Value;
+/// use serde_json::jsonpath::query;
+///
+/// let value: Value = serde_json::from_str(r#"{
+///     "book": [{"author": "A", "price": 8}, {"author": "B", "price": 12}]
+/// }"#).unwrap();
+///
+/// let authors = query(&value, "$.book[*].author").unwrap();
+/// assert_eq!(authors, vec![&Value::String("A".to_owned()),
+///                          &Value::String("B".to_owned())]);
+/// assert_eq!(query(&value, "$..price").unwrap().len(), 2);
+/// assert!(query(&value, "$.book[?(@.price < 10)]").is_err());
+/// ```
+pub fn query<'a>(
+    value: &'a Value,
+    path: &str
+) -> Result<Vec<&'a Value>, PathError> {
+    let steps = try!(parse(path));
+    let mut matches = vec![value];
+    for step in &steps {
+        let mut next = Vec::new();
+        if step.descendants {
+            let mut seen = HashSet::new();
+            let mut nodes = Vec::new();
+            for target in matches {
+                descendants::collect(target, (), &mut seen, &mut nodes);
+            }
+            for (_, target) in nodes {
+                select(target, &step.selector, &mut next);
+            }
+        } else {
+            for target in matches {
+                select(target, &step.selector, &mut next);
+            }
+        }
+        matches = next;
+    }
+    Ok(matches)
+}
+
+fn parse(path: &str) -> Result<Vec<Step>, PathError> {
+    if !path.starts_with('$') {
+        return Err(PathError::MissingRoot);
+    }
+    let mut steps = Vec::new();
+    let mut pos = 1;
+    while pos < path.len() {
+        let rest = &path[pos..];
+        let (descendants, dotted) = if rest.starts_with("..") {
+            pos += 2;
+            (true, true)
+        } else if rest.starts_with('.') {
+            pos += 1;
+            (false, true)
+        } else if rest.starts_with('[') {
+            (false, false)
+        } else {
+            return Err(PathError::InvalidSyntax(pos));
+        };
+        let selector = if (descendants || !dotted) &&
+                          path[pos..].starts_with('[') {
+            try!(parse_bracket(path, &mut pos))
+        } else {
+            try!(parse_name(path, &mut pos))
+        };
+        steps.push(Step {
+            descendants: descendants,
+            selector: selector,
+        });
+    }
+    Ok(steps)
+}
+
+// Parses the member name after a `.`, up to the next `.` or `[`.
+fn parse_name(path: &str, pos: &mut usize) -> Result<Selector, PathError> {
+    let start = *pos;
+    let end = path[start..]
+        .find(|c| c == '.' || c == '[')
+        .map_or(path.len(), |i| start + i);
+    if end == start {
+        return Err(PathError::InvalidSyntax(start));
+    }
+    *pos = end;
+    match &path[start..end] {
+        "*" => Ok(Selector::Wildcard),
+        name => Ok(Selector::Child(name.to_owned())),
+    }
+}
+
+// Parses a bracketed selector, with `pos` at the opening `[`.
+fn parse_bracket(path: &str, pos: &mut usize) -> Result<Selector, PathError> {
+    let start = *pos + 1;
+    let rest = &path[start..];
+    if rest.starts_with('\'') || rest.starts_with('"') {
+        let quote = &rest[..1];
+        let len = match rest[1..].find(quote) {
+            Some(len) => len,
+            None => return Err(PathError::InvalidSyntax(start)),
+        };
+        let close = start + 1 + len + 1;
+        if !path[close..].starts_with(']') {
+            return Err(PathError::InvalidSyntax(close));
+        }
+        *pos = close + 1;
+        return Ok(Selector::Child(rest[1..1 + len].to_owned()));
+    }
+    if rest.starts_with('?') || rest.starts_with('(') {
+        return Err(PathError::Unsupported(start));
+    }
+
+    let close = match rest.find(']') {
+        Some(len) => start + len,
+        None => return Err(PathError::InvalidSyntax(*pos)),
+    };
+    *pos = close + 1;
+    let inner = &path[start..close];
+    if inner == "*" {
+        return Ok(Selector::Wildcard);
+    }
+    if inner.contains(',') {
+        return Err(PathError::Unsupported(start));
+    }
+    let bounds: Vec<&str> = inner.split(':').collect();
+    match bounds.len() {
+        1 => {
+            match inner.parse() {
+                Ok(index) => Ok(Selector::Index(index)),
+                Err(_) => Err(PathError::InvalidSyntax(start)),
+            }
+        }
+        2 => {
+            let from = try!(parse_bound(bounds[0], start));
+            let to = try!(parse_bound(bounds[1], start));
+            Ok(Selector::Slice(from, to))
+        }
+        _ => Err(PathError::Unsupported(start)),
+    }
+}
+
+fn parse_bound(bound: &str, pos: usize) -> Result<Option<i64>, PathError> {
+    if bound.is_empty() {
+        return Ok(None);
+    }
+    match bound.parse() {
+        Ok(n) => Ok(Some(n)),
+        Err(_) => Err(PathError::InvalidSyntax(pos)),
+    }
+}
+
+fn select<'a>(
+    value: &'a Value,
+    selector: &Selector,
+    out: &mut Vec<&'a Value>
+) {
+    match (value, selector) {
+        (&Value::Object(ref map), &Selector::Child(ref name)) => {
+            out.extend(map.get(name));
+        }
+        (&Value::Object(ref map), &Selector::Wildcard) => {
+            out.extend(map.values());
+        }
+        (&Value::Array(ref list), &Selector::Index(index)) => {
+            let len = list.len() as i64;
+            let index = if index < 0 { index + len } else { index };
+            if index >= 0 && index < len {
+                out.push(&list[index as usize]);
+            }
+        }
+        (&Value::Array(ref list), &Selector::Slice(from, to)) => {
+            let len = list.len() as i64;
+            let clamp = |n: i64| {
+                let n = if n < 0 { n + len } else { n };
+                if n < 0 {
+                    0
+                } else if n > len {
+                    len
+                } else {
+                    n
+                }
+            };
+            let from = clamp(from.unwrap_or(0));
+            let to = clamp(to.unwrap_or(len));
+            if from < to {
+                out.extend(&list[from as usize..to as usize]);
+            }
+        }
+        (&Value::Array(ref list), &Selector::Wildcard) => {
+            out.extend(list.iter());
+        }
+        _ => {}
+    }
+}
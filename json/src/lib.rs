@@ -13,6 +13,8 @@
 //! * `I64`: equivalent to rust's `i64`
 //! * `U64`: equivalent to rust's `u64`
 //! * `F64`: equivalent to rust's `f64`
+//! * `BigNumber`: a number that would lose precision as an `I64`, `U64` or `F64`, kept as
+//!    its original text; only available with the `arbitrary_precision` feature
 //! * `String`: equivalent to rust's `String`
 //! * `Array`: equivalent to rust's `Vec<T>`, but also allowing objects of different types in the
 //!    same array
@@ -146,17 +148,37 @@ extern crate dtoa;
 #[cfg(feature = "preserve_order")]
 extern crate linked_hash_map;
 
-pub use self::de::{Deserializer, StreamDeserializer, from_iter, from_reader,
-                   from_slice, from_str};
+pub use self::de::{Deserializer, StreamDeserializer, from_iter, from_ndjson,
+                   from_reader, from_slice, from_str, parse_all_errors};
 pub use self::error::{Error, ErrorCode, Result};
-pub use self::ser::{Serializer, escape_str, to_string, to_string_pretty,
-                    to_vec, to_vec_pretty, to_writer, to_writer_pretty};
+pub use self::ser::{Serializer, escape_str, to_ndjson, to_string,
+                    to_string_auto, to_string_pretty, to_vec, to_vec_pretty,
+                    to_writer, to_writer_pretty};
+pub use self::raw::RawValue;
 pub use self::value::{Map, Value, from_value, to_value};
 
+#[macro_use]
+mod macros;
+
 pub mod builder;
+pub mod comments;
 pub mod de;
 pub mod error;
+pub mod jsonpath;
+pub mod pointer;
+pub mod raw;
 pub mod ser;
 pub mod value;
 
+mod descendants;
+#[cfg(feature = "arbitrary_precision")]
+mod number;
 mod read;
+
+/// Name used to smuggle the text of a `RawValue` through Serde.
+const RAW_TOKEN: &'static str = "$serde_json::private::RawValue";
+
+/// Name used to smuggle a number that does not fit in `u64`, `i64` or `f64`
+/// through Serde as its original text.
+#[cfg(feature = "arbitrary_precision")]
+const NUMBER_TOKEN: &'static str = "$serde_json::private::Number";
@@ -0,0 +1,136 @@
+/// Constructs a `Value` from a JSON literal.
+///
+/// Arrays and objects may be nested, and any other value is an expression
+/// that is converted with `to_value`, so variables can be interpolated.
+/// Object keys are string literals, or any expression in parentheses that
+/// converts into a `String`.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate serde_json;
+///
+/// fn main() {
+///     let code = 200;
+///     let features = vec!["serde", "json"];
+///
+///     let value = json!({
+///         "code": code,
+///         "success": code == 200,
+///         "payload": {
+///             "features": features,
+///             ("count"): [1, 2, null]
+///         }
+///     });
+///
+///     assert_eq!(value.pointer("/success"), Some(&json!(true)));
+///     assert_eq!(value.pointer("/payload/features/1"), Some(&json!("json")));
+///     assert_eq!(value.pointer("/payload/count/2"), Some(&json!(null)));
+/// }
+/// ```
+#[macro_export]
+macro_rules! json {
+    (null) => {
+        $crate::Value::Null
+    };
+
+    ([]) => {
+        $crate::Value::Array(vec![])
+    };
+
+    ([ $($tt:tt)+ ]) => {
+        $crate::Value::Array(json!(@array [] $($tt)+))
+    };
+
+    ({}) => {
+        $crate::Value::Object($crate::Map::new())
+    };
+
+    ({ $($tt:tt)+ }) => {
+        $crate::Value::Object({
+            let mut object = $crate::Map::new();
+            json!(@object object $($tt)+);
+            object
+        })
+    };
+
+    // `null`, arrays and objects are not Rust expressions, so each of them
+    // needs its own rule for use as an element or member value.
+
+    (@array [$($elems:expr,)*]) => {
+        vec![$($elems,)*]
+    };
+
+    (@array [$($elems:expr,)*] null , $($rest:tt)*) => {
+        json!(@array [$($elems,)* $crate::Value::Null,] $($rest)*)
+    };
+
+    (@array [$($elems:expr,)*] null) => {
+        json!(@array [$($elems,)* $crate::Value::Null,])
+    };
+
+    (@array [$($elems:expr,)*] [$($array:tt)*] , $($rest:tt)*) => {
+        json!(@array [$($elems,)* json!([$($array)*]),] $($rest)*)
+    };
+
+    (@array [$($elems:expr,)*] [$($array:tt)*]) => {
+        json!(@array [$($elems,)* json!([$($array)*]),])
+    };
+
+    (@array [$($elems:expr,)*] {$($map:tt)*} , $($rest:tt)*) => {
+        json!(@array [$($elems,)* json!({$($map)*}),] $($rest)*)
+    };
+
+    (@array [$($elems:expr,)*] {$($map:tt)*}) => {
+        json!(@array [$($elems,)* json!({$($map)*}),])
+    };
+
+    (@array [$($elems:expr,)*] $next:expr , $($rest:tt)*) => {
+        json!(@array [$($elems,)* $crate::to_value(&$next),] $($rest)*)
+    };
+
+    (@array [$($elems:expr,)*] $last:expr) => {
+        json!(@array [$($elems,)* $crate::to_value(&$last),])
+    };
+
+    (@object $object:ident) => {};
+
+    (@object $object:ident $key:tt : null , $($rest:tt)*) => {
+        let _ = $object.insert(($key).into(), $crate::Value::Null);
+        json!(@object $object $($rest)*);
+    };
+
+    (@object $object:ident $key:tt : null) => {
+        let _ = $object.insert(($key).into(), $crate::Value::Null);
+    };
+
+    (@object $object:ident $key:tt : [$($array:tt)*] , $($rest:tt)*) => {
+        let _ = $object.insert(($key).into(), json!([$($array)*]));
+        json!(@object $object $($rest)*);
+    };
+
+    (@object $object:ident $key:tt : [$($array:tt)*]) => {
+        let _ = $object.insert(($key).into(), json!([$($array)*]));
+    };
+
+    (@object $object:ident $key:tt : {$($map:tt)*} , $($rest:tt)*) => {
+        let _ = $object.insert(($key).into(), json!({$($map)*}));
+        json!(@object $object $($rest)*);
+    };
+
+    (@object $object:ident $key:tt : {$($map:tt)*}) => {
+        let _ = $object.insert(($key).into(), json!({$($map)*}));
+    };
+
+    (@object $object:ident $key:tt : $value:expr , $($rest:tt)*) => {
+        let _ = $object.insert(($key).into(), $crate::to_value(&$value));
+        json!(@object $object $($rest)*);
+    };
+
+    (@object $object:ident $key:tt : $value:expr) => {
+        let _ = $object.insert(($key).into(), $crate::to_value(&$value));
+    };
+
+    ($other:expr) => {
+        $crate::to_value(&$other)
+    };
+}
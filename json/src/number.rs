@@ -0,0 +1,278 @@
+//! Passing `Value::BigNumber` through Serde
+//!
+//! Serde has no type for a number that does not fit in `u64`, `i64` or
+//! `f64`, so the deserializer offers one to its visitor as a map with a
+//! single entry holding the number's text. The key of that entry is a marker
+//! that a JSON object key can never produce, and only `Value` asks for it, so
+//! every other visitor fails to read the map and is given the nearest `f64`
+//! instead.
+
+use std::result;
+
+use serde::de;
+
+use error::{Error, Result};
+
+/// Returns true if `s` is a JSON number, and so safe to write out unquoted.
+pub fn is_valid(s: &str) -> bool {
+    let s = s.as_bytes();
+    let mut i = 0;
+    if s.first() == Some(&b'-') {
+        i += 1;
+    }
+    match s.get(i) {
+        Some(&b'0') => i += 1,
+        Some(&b'1'...b'9') => i = digits(s, i + 1),
+        _ => return false,
+    }
+    if s.get(i) == Some(&b'.') {
+        let start = i + 1;
+        i = digits(s, start);
+        if i == start {
+            return false;
+        }
+    }
+    if let Some(&b'e') | Some(&b'E') = s.get(i) {
+        i += 1;
+        if let Some(&b'+') | Some(&b'-') = s.get(i) {
+            i += 1;
+        }
+        let start = i;
+        i = digits(s, start);
+        if i == start {
+            return false;
+        }
+    }
+    i == s.len()
+}
+
+fn digits(s: &[u8], mut i: usize) -> usize {
+    while let Some(&b'0'...b'9') = s.get(i) {
+        i += 1;
+    }
+    i
+}
+
+/// Returns true if `number`, a JSON number, has the same value as `f`
+/// written out with the fewest digits that read back as `f`, so nothing is
+/// lost by keeping `f` instead of the text.
+pub fn round_trips(number: &str, f: f64) -> bool {
+    match (decimal(number), decimal(&format!("{:?}", f))) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+// Splits a number into its sign, its significant digits and the power of ten
+// of the last of them. Returns None if that power does not fit in an `i64`.
+fn decimal(s: &str) -> Option<(bool, String, i64)> {
+    let negative = s.starts_with('-');
+    let s = s.trim_left_matches('-');
+    let (mantissa, exponent) = match s.find(|c| c == 'e' || c == 'E') {
+        Some(i) => (&s[..i], s[i + 1..].trim_left_matches('+')),
+        None => (s, "0"),
+    };
+
+    let mut digits = String::with_capacity(mantissa.len());
+    let mut fraction_len = 0;
+    for c in mantissa.chars() {
+        if c == '.' {
+            fraction_len = mantissa.len() - digits.len() - 1;
+        } else {
+            digits.push(c);
+        }
+    }
+    let digits = digits.trim_left_matches('0');
+    let significant = digits.trim_right_matches('0');
+    if significant.is_empty() {
+        // Zero, whatever the exponent.
+        return Some((negative, String::new(), 0));
+    }
+
+    let exponent: i64 = match exponent.parse() {
+        Ok(exponent) => exponent,
+        Err(_) => return None,
+    };
+    let zeros = (digits.len() - significant.len()) as i64;
+    exponent.checked_add(zeros)
+        .and_then(|exponent| exponent.checked_sub(fraction_len as i64))
+        .map(|exponent| (negative, significant.to_owned(), exponent))
+}
+
+/// Offers `number` to the visitor as the private single-entry map.
+pub fn visit<V>(visitor: &mut V, number: &str) -> Result<V::Value>
+    where V: de::Visitor,
+{
+    visitor.visit_map(NumberMap {
+        number: Some(number),
+    })
+}
+
+/// A number as the parser read it, before it is handed to the real visitor.
+pub enum Parsed {
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Big(String),
+}
+
+/// Reads a number into `Parsed`.
+pub struct ParsedVisitor;
+
+impl de::Deserialize for Parsed {
+    fn deserialize<D>(deserializer: &mut D) -> result::Result<Parsed, D::Error>
+        where D: de::Deserializer,
+    {
+        deserializer.deserialize(ParsedVisitor)
+    }
+}
+
+impl de::Visitor for ParsedVisitor {
+    type Value = Parsed;
+
+    fn visit_u64<E>(&mut self, n: u64) -> result::Result<Parsed, E> {
+        Ok(Parsed::U64(n))
+    }
+
+    fn visit_i64<E>(&mut self, n: i64) -> result::Result<Parsed, E> {
+        Ok(Parsed::I64(n))
+    }
+
+    fn visit_f64<E>(&mut self, n: f64) -> result::Result<Parsed, E> {
+        Ok(Parsed::F64(n))
+    }
+
+    fn visit_map<V>(
+        &mut self,
+        mut visitor: V
+    ) -> result::Result<Parsed, V::Error>
+        where V: de::MapVisitor,
+    {
+        match try!(visitor.visit_key()) {
+            Some(MapKey::Number) => {
+                let number = try!(visitor.visit_value());
+                try!(visitor.end());
+                Ok(Parsed::Big(number))
+            }
+            _ => Err(de::Error::invalid_type(de::Type::Map)),
+        }
+    }
+}
+
+/// A key of a map given to `Value`: either an object key, or the marker that
+/// the map holds a big number.
+pub enum MapKey {
+    Key(String),
+    Number,
+}
+
+impl de::Deserialize for MapKey {
+    fn deserialize<D>(deserializer: &mut D) -> result::Result<MapKey, D::Error>
+        where D: de::Deserializer,
+    {
+        struct MapKeyVisitor;
+
+        impl de::Visitor for MapKeyVisitor {
+            type Value = MapKey;
+
+            // Only `NumberKey` calls this.
+            fn visit_unit<E>(&mut self) -> result::Result<MapKey, E> {
+                Ok(MapKey::Number)
+            }
+
+            fn visit_str<E>(&mut self, key: &str) -> result::Result<MapKey, E> {
+                Ok(MapKey::Key(key.to_owned()))
+            }
+
+            fn visit_string<E>(
+                &mut self,
+                key: String
+            ) -> result::Result<MapKey, E> {
+                Ok(MapKey::Key(key))
+            }
+
+            fn visit_newtype_struct<D>(
+                &mut self,
+                deserializer: &mut D
+            ) -> result::Result<MapKey, D::Error>
+                where D: de::Deserializer,
+            {
+                de::Deserialize::deserialize(deserializer).map(MapKey::Key)
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(::NUMBER_TOKEN, MapKeyVisitor)
+    }
+}
+
+struct NumberMap<'a> {
+    number: Option<&'a str>,
+}
+
+impl<'a> de::MapVisitor for NumberMap<'a> {
+    type Error = Error;
+
+    fn visit_key<K>(&mut self) -> Result<Option<K>>
+        where K: de::Deserialize,
+    {
+        match self.number {
+            Some(_) => de::Deserialize::deserialize(&mut NumberKey).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn visit_value<V>(&mut self) -> Result<V>
+        where V: de::Deserialize,
+    {
+        match self.number.take() {
+            Some(number) => {
+                let mut deserializer =
+                    de::value::ValueDeserializer::<Error>::into_deserializer(
+                        number);
+                de::Deserialize::deserialize(&mut deserializer)
+            }
+            None => Err(de::Error::end_of_stream()),
+        }
+    }
+
+    fn end(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (1, Some(1))
+    }
+}
+
+// Deserializes the marker key. Only `MapKey` can read it.
+struct NumberKey;
+
+impl de::Deserializer for NumberKey {
+    type Error = Error;
+
+    fn deserialize<V>(&mut self, _visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        Err(de::Error::invalid_type(de::Type::Map))
+    }
+
+    fn deserialize_newtype_struct<V>(
+        &mut self,
+        name: &'static str,
+        mut visitor: V
+    ) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        if name == ::NUMBER_TOKEN {
+            visitor.visit_unit()
+        } else {
+            self.deserialize(visitor)
+        }
+    }
+
+    forward_to_deserialize! {
+        bool usize u8 u16 u32 u64 isize i8 i16 i32 i64 f32 f64 char str string
+        unit option seq seq_fixed_size bytes map unit_struct tuple_struct
+        struct struct_field tuple enum ignored_any
+    }
+}
@@ -0,0 +1,202 @@
+//! JSON Pointer helpers
+//!
+//! This module provides functions for working with
+//! [RFC6901](https://tools.ietf.org/html/rfc6901) JSON Pointer strings
+//! directly, for tools that need the individual reference tokens rather than
+//! the `Value` they point to.
+
+use std::error;
+use std::fmt;
+
+/// This type represents the ways a JSON Pointer string can be malformed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PointerError {
+    /// A non-empty pointer did not start with a `/`.
+    MissingLeadingSlash,
+
+    /// A `~` in a reference token was not followed by `0` or `1`.
+    InvalidEscape,
+}
+
+impl error::Error for PointerError {
+    fn description(&self) -> &str {
+        match *self {
+            PointerError::MissingLeadingSlash => "pointer must start with `/`",
+            PointerError::InvalidEscape => "invalid pointer escape",
+        }
+    }
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PointerError::MissingLeadingSlash => {
+                "pointer must start with `/`".fmt(f)
+            }
+            PointerError::InvalidEscape => {
+                "`~` must be followed by `0` or `1`".fmt(f)
+            }
+        }
+    }
+}
+
+/// This type represents the ways a set of JSON Pointers that must address
+/// separate locations can be rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PointerSetError {
+    /// The pointer is malformed.
+    Invalid(String, PointerError),
+
+    /// The pointer addresses a location that another pointer in the same set
+    /// already claims, such as `/a` and `/a/b`.
+    Conflict(String),
+}
+
+impl error::Error for PointerSetError {
+    fn description(&self) -> &str {
+        match *self {
+            PointerSetError::Invalid(_, ref err) => err.description(),
+            PointerSetError::Conflict(_) => "conflicting pointer",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            PointerSetError::Invalid(_, ref err) => Some(err),
+            PointerSetError::Conflict(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for PointerSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PointerSetError::Invalid(ref pointer, ref err) => {
+                write!(f, "{} in pointer \"{}\"", err, pointer)
+            }
+            PointerSetError::Conflict(ref pointer) => {
+                write!(f, "conflicting pointer \"{}\"", pointer)
+            }
+        }
+    }
+}
+
+/// Splits a JSON Pointer into its unescaped reference tokens.
+///
+/// The empty pointer refers to the whole document and has no tokens.
+///
+/// ```rust
+/// use serde_json::pointer::parse_pointer;
+///
+/// assert_eq!(parse_pointer("/a~1b/0").unwrap(), vec!["a/b", "0"]);
+/// assert_eq!(parse_pointer("").unwrap(), Vec::<String>::new());
+/// assert!(parse_pointer("abc").is_err());
+/// ```
+pub fn parse_pointer(pointer: &str) -> Result<Vec<String>, PointerError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return Err(PointerError::MissingLeadingSlash);
+    }
+    pointer.split('/').skip(1).map(unescape).collect()
+}
+
+/// Checks that `pointer` is a syntactically valid JSON Pointer: either empty,
+/// or starting with `/` and with every `~` followed by `0` or `1`.
+///
+/// This says nothing about whether the pointer resolves in any particular
+/// document. `Value::pointer` is more lenient and treats a malformed escape
+/// such as `~2` as literal text.
+///
+/// ```rust
+/// use serde_json::pointer::{PointerError, validate_pointer};
+///
+/// assert_eq!(validate_pointer("/a~1b/~0"), Ok(()));
+/// assert_eq!(validate_pointer("/a~2b"), Err(PointerError::InvalidEscape));
+/// assert_eq!(validate_pointer("a"), Err(PointerError::MissingLeadingSlash));
+/// ```
+pub fn validate_pointer(pointer: &str) -> Result<(), PointerError> {
+    if pointer.is_empty() {
+        return Ok(());
+    }
+    if !pointer.starts_with('/') {
+        return Err(PointerError::MissingLeadingSlash);
+    }
+    let mut bytes = pointer.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'~' {
+            match bytes.next() {
+                Some(b'0') | Some(b'1') => {}
+                _ => return Err(PointerError::InvalidEscape),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if `pointer` is a syntactically valid JSON Pointer, as
+/// checked by `validate_pointer`.
+pub fn is_valid_pointer(pointer: &str) -> bool {
+    validate_pointer(pointer).is_ok()
+}
+
+/// Appends `token` to the pointer `base`, escaping any `~` or `/` in it.
+///
+/// ```rust
+/// use serde_json::pointer::join;
+///
+/// assert_eq!(join("", "a"), "/a");
+/// assert_eq!(join("/a", "b/c"), "/a/b~1c");
+/// ```
+pub fn join(base: &str, token: &str) -> String {
+    let mut pointer = String::with_capacity(base.len() + token.len() + 1);
+    pointer.push_str(base);
+    pointer.push('/');
+    for c in token.chars() {
+        match c {
+            '~' => pointer.push_str("~0"),
+            '/' => pointer.push_str("~1"),
+            c => pointer.push(c),
+        }
+    }
+    pointer
+}
+
+/// Returns `pointer` without its last reference token.
+///
+/// The whole document, `""`, has no parent so this returns None for it, as
+/// it does for any pointer that `validate_pointer` rejects. `"/"` refers to
+/// the `""` key of the root object, so its parent is `""`.
+///
+/// ```rust
+/// use serde_json::pointer::parent;
+///
+/// assert_eq!(parent("/a/b~1c"), Some("/a"));
+/// assert_eq!(parent("/"), Some(""));
+/// assert_eq!(parent(""), None);
+/// assert_eq!(parent("/a~2/b"), None);
+/// ```
+pub fn parent(pointer: &str) -> Option<&str> {
+    if !is_valid_pointer(pointer) {
+        return None;
+    }
+    pointer.rfind('/').map(|i| &pointer[..i])
+}
+
+fn unescape(token: &str) -> Result<String, PointerError> {
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c == '~' {
+            match chars.next() {
+                Some('0') => unescaped.push('~'),
+                Some('1') => unescaped.push('/'),
+                _ => return Err(PointerError::InvalidEscape),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Ok(unescaped)
+}
@@ -0,0 +1,122 @@
+//! Unparsed JSON
+//!
+//! This module provides `RawValue`, which holds a piece of JSON text as
+//! written, for data that a program passes through without looking at.
+
+use std::fmt;
+
+use serde::{de, ser};
+
+use de::ParseOptions;
+use error::Error;
+use value::Value;
+
+/// A JSON value kept as its original text.
+///
+/// Deserializing a `RawValue` with this crate checks that the input is valid
+/// JSON but keeps the exact text, including its whitespace, key order and
+/// number formatting, instead of building the value. Serializing it with this
+/// crate writes that text back out byte for byte. This makes it possible to
+/// pass a subtree of a document through untouched while working with the
+/// rest of it.
+///
+/// ```rust
+/// extern crate serde;
+/// extern crate serde_json;
+///
+/// use std::collections::BTreeMap;
+/// use serde_json::RawValue;
+///
+/// fn main() {
+///     let input = r#"{"payload": {"z": 1.50,  "a": [ ]}}"#;
+///     let map: BTreeMap<String, RawValue> = serde_json::from_str(input).unwrap();
+///     assert_eq!(map["payload"].get(), r#"{"z": 1.50,  "a": [ ]}"#);
+///     assert_eq!(serde_json::to_string(&map).unwrap(),
+///                r#"{"payload":{"z": 1.50,  "a": [ ]}}"#);
+/// }
+/// ```
+///
+/// Other Serde formats see a `RawValue` as a newtype around its text.
+/// Deserializing one from anything other than this crate's `Deserializer`
+/// builds the value and writes it out as compact JSON.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawValue {
+    json: String,
+}
+
+impl RawValue {
+    /// Checks that `json` is a single valid JSON value and wraps it without
+    /// changing it. Whitespace around the value is kept.
+    pub fn from_string(json: String) -> Result<RawValue, Error> {
+        try!(ParseOptions::new().from_str::<de::impls::IgnoredAny>(&json));
+        Ok(RawValue {
+            json: json,
+        })
+    }
+
+    /// Returns the JSON text.
+    pub fn get(&self) -> &str {
+        &self.json
+    }
+
+    /// Consumes the `RawValue`, returning the JSON text.
+    pub fn into_string(self) -> String {
+        self.json
+    }
+}
+
+impl fmt::Display for RawValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.json.fmt(f)
+    }
+}
+
+impl ser::Serialize for RawValue {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: ser::Serializer,
+    {
+        serializer.serialize_newtype_struct(::RAW_TOKEN, &self.json)
+    }
+}
+
+impl de::Deserialize for RawValue {
+    fn deserialize<D>(deserializer: &mut D) -> Result<RawValue, D::Error>
+        where D: de::Deserializer,
+    {
+        struct RawValueVisitor;
+
+        impl de::Visitor for RawValueVisitor {
+            type Value = RawValue;
+
+            // This crate's deserializer calls this with the raw text, but so
+            // can any other, so the text is checked before it is kept.
+            fn visit_string<E>(&mut self, json: String) -> Result<RawValue, E>
+                where E: de::Error,
+            {
+                RawValue::from_string(json)
+                    .map_err(|err| E::invalid_value(&err.to_string()))
+            }
+
+            fn visit_str<E>(&mut self, json: &str) -> Result<RawValue, E>
+                where E: de::Error,
+            {
+                self.visit_string(json.to_owned())
+            }
+
+            fn visit_newtype_struct<D>(
+                &mut self,
+                deserializer: &mut D
+            ) -> Result<RawValue, D::Error>
+                where D: de::Deserializer,
+            {
+                let value: Value =
+                    try!(de::Deserialize::deserialize(deserializer));
+                Ok(RawValue {
+                    json: value.to_string(),
+                })
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(::RAW_TOKEN, RawValueVisitor)
+    }
+}
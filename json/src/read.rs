@@ -35,10 +35,24 @@ pub trait Read {
     /// Assumes the previous byte was a quotation mark. Parses a JSON-escaped
     /// string until the next quotation mark using the given scratch space if
     /// necessary. The scratch space is initially empty.
+    ///
+    /// Fails with `StringTooLong` as soon as the unescaped string is known to
+    /// be longer than `max_len` bytes.
     fn parse_str<'s>(
         &'s mut self,
-        scratch: &'s mut Vec<u8>
+        scratch: &'s mut Vec<u8>,
+        max_len: usize
     ) -> Result<&'s str>;
+
+    /// Starts recording every byte consumed by next() or discard(), to be
+    /// returned by end_raw_buffering(). A byte that was peeked but not yet
+    /// consumed is recorded when it is consumed. Recordings may nest; the
+    /// returned mark identifies this one.
+    fn begin_raw_buffering(&mut self) -> usize;
+
+    /// Stops the recording that returned `mark` and returns the bytes
+    /// consumed since it began.
+    fn end_raw_buffering(&mut self, mark: usize) -> Vec<u8>;
 }
 
 pub struct Position {
@@ -52,6 +66,10 @@ pub struct IteratorRead<Iter>
     iter: LineColIterator<Iter>,
     /// Temporary storage of peeked byte.
     ch: Option<u8>,
+    /// Consumed bytes, while raw buffering is on.
+    raw_buffer: Option<Vec<u8>>,
+    /// Number of recordings in progress.
+    raw_depth: usize,
 }
 
 /// Specialization for Iter=&[u8]. This is more efficient than other iterators
@@ -77,6 +95,8 @@ impl<Iter> IteratorRead<Iter>
         IteratorRead {
             iter: LineColIterator::new(iter),
             ch: None,
+            raw_buffer: None,
+            raw_depth: 0,
         }
     }
 }
@@ -86,16 +106,20 @@ impl<Iter> Read for IteratorRead<Iter>
 {
     #[inline]
     fn next(&mut self) -> io::Result<Option<u8>> {
-        match self.ch.take() {
-            Some(ch) => Ok(Some(ch)),
+        let ch = match self.ch.take() {
+            Some(ch) => Some(ch),
             None => {
                 match self.iter.next() {
-                    Some(Err(err)) => Err(err),
-                    Some(Ok(ch)) => Ok(Some(ch)),
-                    None => Ok(None),
+                    Some(Err(err)) => return Err(err),
+                    Some(Ok(ch)) => Some(ch),
+                    None => None,
                 }
             }
+        };
+        if let Some(ref mut buf) = self.raw_buffer {
+            buf.extend(ch);
         }
+        Ok(ch)
     }
 
     #[inline]
@@ -117,7 +141,10 @@ impl<Iter> Read for IteratorRead<Iter>
 
     #[inline]
     fn discard(&mut self) {
-        self.ch = None;
+        let ch = self.ch.take();
+        if let Some(ref mut buf) = self.raw_buffer {
+            buf.extend(ch);
+        }
     }
 
     fn position(&self) -> Position {
@@ -135,9 +162,13 @@ impl<Iter> Read for IteratorRead<Iter>
 
     fn parse_str<'s>(
         &'s mut self,
-        scratch: &'s mut Vec<u8>
+        scratch: &'s mut Vec<u8>,
+        max_len: usize
     ) -> Result<&'s str> {
         loop {
+            if scratch.len() > max_len {
+                return error(self, ErrorCode::StringTooLong);
+            }
             let ch = match try!(self.next().map_err(Error::Io)) {
                 Some(ch) => ch,
                 None => {
@@ -161,6 +192,25 @@ impl<Iter> Read for IteratorRead<Iter>
             }
         }
     }
+
+    fn begin_raw_buffering(&mut self) -> usize {
+        self.raw_depth += 1;
+        if self.raw_buffer.is_none() {
+            self.raw_buffer = Some(Vec::new());
+        }
+        self.raw_buffer.as_ref().map_or(0, Vec::len)
+    }
+
+    fn end_raw_buffering(&mut self, mark: usize) -> Vec<u8> {
+        self.raw_depth -= 1;
+        if self.raw_depth == 0 {
+            self.raw_buffer.take().unwrap_or_else(Vec::new)
+        } else {
+            self.raw_buffer
+                .as_ref()
+                .map_or_else(Vec::new, |buf| buf[mark..].to_vec())
+        }
+    }
 }
 
 //////////////////////////////////////////////////////////////////////////////
@@ -198,6 +248,7 @@ impl<'a> SliceRead<'a> {
     fn parse_str_bytes<'s, T, F>(
         &'s mut self,
         scratch: &'s mut Vec<u8>,
+        max_len: usize,
         result: F
     ) -> Result<T>
         where T: 's,
@@ -214,6 +265,9 @@ impl<'a> SliceRead<'a> {
             if self.index == self.slice.len() {
                 return error(self, ErrorCode::EOFWhileParsingString);
             }
+            if scratch.len() + (self.index - start) > max_len {
+                return error(self, ErrorCode::StringTooLong);
+            }
             match self.slice[self.index] {
                 b'"' => {
                     let string = if scratch.is_empty() {
@@ -284,9 +338,18 @@ impl<'a> Read for SliceRead<'a> {
 
     fn parse_str<'s>(
         &'s mut self,
-        scratch: &'s mut Vec<u8>
+        scratch: &'s mut Vec<u8>,
+        max_len: usize
     ) -> Result<&'s str> {
-        self.parse_str_bytes(scratch, as_str)
+        self.parse_str_bytes(scratch, max_len, as_str)
+    }
+
+    fn begin_raw_buffering(&mut self) -> usize {
+        self.index
+    }
+
+    fn end_raw_buffering(&mut self, mark: usize) -> Vec<u8> {
+        self.slice[mark..self.index].to_vec()
     }
 }
 
@@ -326,14 +389,23 @@ impl<'a> Read for StrRead<'a> {
 
     fn parse_str<'s>(
         &'s mut self,
-        scratch: &'s mut Vec<u8>
+        scratch: &'s mut Vec<u8>,
+        max_len: usize
     ) -> Result<&'s str> {
-        self.delegate.parse_str_bytes(scratch, |_, bytes| {
+        self.delegate.parse_str_bytes(scratch, max_len, |_, bytes| {
             // The input is assumed to be valid UTF-8 and the \u-escapes are
             // checked along the way, so don't need to check here.
             Ok(unsafe { str::from_utf8_unchecked(bytes) })
         })
     }
+
+    fn begin_raw_buffering(&mut self) -> usize {
+        self.delegate.begin_raw_buffering()
+    }
+
+    fn end_raw_buffering(&mut self, mark: usize) -> Vec<u8> {
+        self.delegate.end_raw_buffering(mark)
+    }
 }
 
 //////////////////////////////////////////////////////////////////////////////
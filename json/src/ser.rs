@@ -7,6 +7,7 @@ use std::num::FpCategory;
 
 use serde::ser;
 use super::error::{Error, ErrorCode, Result};
+use super::value::Value;
 
 use itoa;
 use dtoa;
@@ -15,6 +16,150 @@ use dtoa;
 pub struct Serializer<W, F = CompactFormatter> {
     writer: W,
     formatter: F,
+    options: SerializeOptions,
+}
+
+/// Options controlling how values without a standard JSON representation are
+/// written.
+///
+/// A configured `SerializeOptions` can be kept and reused for any number of
+/// values through its `to_writer`, `to_vec` and `to_string` methods, which
+/// also honor the `pretty` and `indent` options.
+///
+/// ```rust
+/// use serde_json::ser::{FloatFormat, SerializeOptions};
+///
+/// let options = SerializeOptions::new()
+///     .pretty(true)
+///     .indent(4)
+///     .float_format(FloatFormat::FixedPrecision(1));
+/// assert_eq!(options.to_string(&vec![1.25]).unwrap(), "[\n    1.2\n]");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SerializeOptions {
+    emit_non_finite: bool,
+    float_format: FloatFormat,
+    escape_non_ascii: bool,
+    pretty: bool,
+    indent: Option<usize>,
+}
+
+/// How finite floating point numbers are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatFormat {
+    /// The shortest representation that parses back to the same value. It
+    /// always has a fractional part or exponent, so `1.0` is written as `1.0`
+    /// and is read back as a float.
+    Shortest,
+
+    /// Exactly this many digits after the decimal point, rounding as needed.
+    FixedPrecision(usize),
+
+    /// Like `Shortest`, except that floats holding an integer that is exactly
+    /// representable, up to 2<sup>53</sup> in magnitude, are written without
+    /// a fractional part, so `1.0` is written as `1`.
+    PreserveInteger,
+}
+
+impl Default for FloatFormat {
+    fn default() -> Self {
+        FloatFormat::Shortest
+    }
+}
+
+impl SerializeOptions {
+    /// Creates the default set of options.
+    pub fn new() -> Self {
+        SerializeOptions::default()
+    }
+
+    /// Write non-finite floats as the `NaN`, `Infinity` and `-Infinity`
+    /// tokens understood by `ParseOptions::allow_non_finite`. Otherwise
+    /// serializing a non-finite float fails with `ErrorCode::NonFiniteFloat`,
+    /// since standard JSON cannot represent it.
+    pub fn emit_non_finite(mut self, emit: bool) -> Self {
+        self.emit_non_finite = emit;
+        self
+    }
+
+    /// Set how finite floats are written. Defaults to `FloatFormat::Shortest`.
+    pub fn float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = format;
+        self
+    }
+
+    /// Write every non-ASCII character in strings as a `\uXXXX` escape,
+    /// using a UTF-16 surrogate pair for characters outside the Basic
+    /// Multilingual Plane, so that the output is pure ASCII.
+    pub fn escape_non_ascii(mut self, escape: bool) -> Self {
+        self.escape_non_ascii = escape;
+        self
+    }
+
+    /// Write one value per line with nested values indented, as
+    /// `to_writer_pretty` does, rather than compactly.
+    ///
+    /// This and `indent` apply to the `to_*` methods of `SerializeOptions`; a
+    /// `Serializer` lays out its output with the formatter it was given.
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Indent pretty output by `width` spaces per level. Defaults to two.
+    pub fn indent(mut self, width: usize) -> Self {
+        self.indent = Some(width);
+        self
+    }
+
+    /// Encode the specified struct into a json `[u8]` writer using these
+    /// options.
+    pub fn to_writer<W: ?Sized, T>(
+        &self,
+        writer: &mut W,
+        value: &T
+    ) -> Result<()>
+        where W: io::Write,
+              T: ser::Serialize,
+    {
+        if self.pretty {
+            let indent = vec![b' '; self.indent.unwrap_or(2)];
+            let formatter = PrettyFormatter::with_indent(&indent);
+            let mut ser = Serializer::with_options(writer,
+                                                   formatter,
+                                                   self.clone());
+            try!(value.serialize(&mut ser));
+        } else {
+            let mut ser = Serializer::with_options(writer,
+                                                   CompactFormatter,
+                                                   self.clone());
+            try!(value.serialize(&mut ser));
+        }
+        Ok(())
+    }
+
+    /// Encode the specified struct into a json `[u8]` buffer using these
+    /// options.
+    pub fn to_vec<T>(&self, value: &T) -> Result<Vec<u8>>
+        where T: ser::Serialize,
+    {
+        let mut writer = Vec::with_capacity(128);
+        try!(self.to_writer(&mut writer, value));
+        Ok(writer)
+    }
+
+    /// Encode the specified struct into a json `String` buffer using these
+    /// options.
+    pub fn to_string<T>(&self, value: &T) -> Result<String>
+        where T: ser::Serialize,
+    {
+        let vec = try!(self.to_vec(value));
+        let string = unsafe {
+            // We do not emit invalid UTF-8.
+            String::from_utf8_unchecked(vec)
+        };
+        Ok(string)
+    }
 }
 
 impl<W> Serializer<W>
@@ -45,9 +190,21 @@ impl<W, F> Serializer<W, F>
     /// specified.
     #[inline]
     pub fn with_formatter(writer: W, formatter: F) -> Self {
+        Serializer::with_options(writer, formatter, SerializeOptions::default())
+    }
+
+    /// Creates a new JSON visitor that writes to the writer specified using
+    /// the given formatter and options.
+    #[inline]
+    pub fn with_options(
+        writer: W,
+        formatter: F,
+        options: SerializeOptions
+    ) -> Self {
         Serializer {
             writer: writer,
             formatter: formatter,
+            options: options,
         }
     }
 
@@ -56,6 +213,14 @@ impl<W, F> Serializer<W, F>
     pub fn into_inner(self) -> W {
         self.writer
     }
+
+    fn serialize_non_finite(&mut self, value: f64) -> Result<()> {
+        if self.options.emit_non_finite {
+            fmt_non_finite(&mut self.writer, value)
+        } else {
+            Err(Error::Syntax(ErrorCode::NonFiniteFloat, 0, 0))
+        }
+    }
 }
 
 #[doc(hidden)]
@@ -141,21 +306,41 @@ impl<W, F> ser::Serializer for Serializer<W, F>
 
     #[inline]
     fn serialize_f32(&mut self, value: f32) -> Result<()> {
-        fmt_f32_or_null(&mut self.writer, value).map_err(From::from)
+        if !value.is_finite() {
+            return self.serialize_non_finite(value as f64);
+        }
+        match self.options.float_format {
+            FloatFormat::Shortest => fmt_f32_or_null(&mut self.writer, value),
+            FloatFormat::PreserveInteger if !is_exact_integer(value as f64) => {
+                fmt_f32_or_null(&mut self.writer, value)
+            }
+            format => fmt_f64_with(&mut self.writer, value as f64, format),
+        }
     }
 
     #[inline]
     fn serialize_f64(&mut self, value: f64) -> Result<()> {
-        fmt_f64_or_null(&mut self.writer, value).map_err(From::from)
+        if !value.is_finite() {
+            return self.serialize_non_finite(value);
+        }
+        fmt_f64_with(&mut self.writer, value, self.options.float_format)
     }
 
     #[inline]
     fn serialize_char(&mut self, value: char) -> Result<()> {
+        if self.options.escape_non_ascii {
+            let mut s = String::new();
+            s.push(value);
+            return escape_str_ascii(&mut self.writer, &s);
+        }
         escape_char(&mut self.writer, value).map_err(From::from)
     }
 
     #[inline]
     fn serialize_str(&mut self, value: &str) -> Result<()> {
+        if self.options.escape_non_ascii {
+            return escape_str_ascii(&mut self.writer, value);
+        }
         escape_str(&mut self.writer, value).map_err(From::from)
     }
 
@@ -192,11 +377,32 @@ impl<W, F> ser::Serializer for Serializer<W, F>
     #[inline]
     fn serialize_newtype_struct<T>(
         &mut self,
-        _name: &'static str,
+        name: &'static str,
         value: T
     ) -> Result<()>
         where T: ser::Serialize,
     {
+        if name == ::RAW_TOKEN {
+            if let ::value::Value::String(raw) = ::value::to_value(&value) {
+                return self.writer
+                    .write_all(raw.as_bytes())
+                    .map_err(From::from);
+            }
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        {
+            if name == ::NUMBER_TOKEN {
+                if let ::value::Value::String(number) = ::value::to_value(&value) {
+                    if !::number::is_valid(&number) {
+                        return Err(Error::Syntax(ErrorCode::InvalidNumber, 0,
+                                                 0));
+                    }
+                    return self.writer
+                        .write_all(number.as_bytes())
+                        .map_err(From::from);
+                }
+            }
+        }
         value.serialize(self)
     }
 
@@ -441,6 +647,81 @@ impl<W, F> ser::Serializer for Serializer<W, F>
     }
 }
 
+/// Writes a JSON array one element at a time, so that a large array can be
+/// written without holding all of its elements in memory.
+///
+/// Each element is written to the underlying writer as soon as it is pushed.
+/// The output is identical to serializing the same elements as a `Vec` with
+/// the same formatter.
+///
+/// ```rust
+/// use serde_json::ser::ArraySerializer;
+///
+/// let mut array = ArraySerializer::new(Vec::new());
+/// for i in 0..3 {
+///     array.push(&i).unwrap();
+/// }
+/// let json = array.end().unwrap();
+/// assert_eq!(json, b"[0,1,2]");
+/// ```
+pub struct ArraySerializer<W, F = CompactFormatter> {
+    ser: Serializer<W, F>,
+    state: State,
+}
+
+impl<W> ArraySerializer<W>
+    where W: io::Write,
+{
+    /// Creates an array serializer that writes compact JSON.
+    #[inline]
+    pub fn new(writer: W) -> Self {
+        ArraySerializer::with_serializer(Serializer::new(writer))
+    }
+}
+
+impl<W, F> ArraySerializer<W, F>
+    where W: io::Write,
+          F: Formatter,
+{
+    /// Creates an array serializer that writes through the given serializer,
+    /// using its formatter and options.
+    #[inline]
+    pub fn with_serializer(ser: Serializer<W, F>) -> Self {
+        ArraySerializer {
+            ser: ser,
+            state: State::Empty,
+        }
+    }
+
+    /// Writes the next element of the array.
+    pub fn push<T: ?Sized>(&mut self, value: &T) -> Result<()>
+        where T: ser::Serialize,
+    {
+        // The opening bracket is deferred so that an empty array is written
+        // as `[]` by every formatter.
+        if self.state == State::Empty {
+            try!(self.ser.formatter.open(&mut self.ser.writer, b'['));
+            self.state = State::First;
+        }
+        try!(self.ser
+            .formatter
+            .comma(&mut self.ser.writer, self.state == State::First));
+        self.state = State::Rest;
+
+        value.serialize(&mut self.ser)
+    }
+
+    /// Writes the end of the array, flushes the writer and returns it.
+    pub fn end(mut self) -> Result<W> {
+        match self.state {
+            State::Empty => try!(self.ser.writer.write_all(b"[]")),
+            _ => try!(self.ser.formatter.close(&mut self.ser.writer, b']')),
+        }
+        try!(self.ser.writer.flush());
+        Ok(self.ser.into_inner())
+    }
+}
+
 struct MapKeySerializer<'a, W: 'a, F: 'a> {
     ser: &'a mut Serializer<W, F>,
 }
@@ -861,13 +1142,7 @@ pub fn escape_str<W>(wr: &mut W, value: &str) -> Result<()>
         }
 
         if escape == b'u' {
-            static HEX_DIGITS: [u8; 16] = *b"0123456789abcdef";
-            try!(wr.write_all(&[b'\\',
-                                b'u',
-                                b'0',
-                                b'0',
-                                HEX_DIGITS[(byte >> 4) as usize],
-                                HEX_DIGITS[(byte & 0xF) as usize]]));
+            try!(write_unicode_escape(wr, byte as u16));
         } else {
             try!(wr.write_all(&[b'\\', escape]));
         }
@@ -883,6 +1158,65 @@ pub fn escape_str<W>(wr: &mut W, value: &str) -> Result<()>
     Ok(())
 }
 
+// Like `escape_str`, but also escapes every non-ASCII character as one or two
+// UTF-16 code units.
+fn escape_str_ascii<W>(wr: &mut W, value: &str) -> Result<()>
+    where W: io::Write,
+{
+    let bytes = value.as_bytes();
+
+    try!(wr.write_all(b"\""));
+
+    let mut start = 0;
+
+    for (i, ch) in value.char_indices() {
+        let escape = if (ch as u32) < 0x80 {
+            ESCAPE[ch as usize]
+        } else {
+            U
+        };
+        if escape == 0 {
+            continue;
+        }
+
+        if start < i {
+            try!(wr.write_all(&bytes[start..i]));
+        }
+
+        if escape == b'u' {
+            let mut units = [0; 2];
+            for &unit in ch.encode_utf16(&mut units).iter() {
+                try!(write_unicode_escape(wr, unit));
+            }
+        } else {
+            try!(wr.write_all(&[b'\\', escape]));
+        }
+
+        start = i + ch.len_utf8();
+    }
+
+    if start != bytes.len() {
+        try!(wr.write_all(&bytes[start..]));
+    }
+
+    try!(wr.write_all(b"\""));
+    Ok(())
+}
+
+#[inline]
+fn write_unicode_escape<W>(wr: &mut W, unit: u16) -> Result<()>
+    where W: io::Write,
+{
+    static HEX_DIGITS: [u8; 16] = *b"0123456789abcdef";
+    try!(wr.write_all(&[b'\\',
+                        b'u',
+                        HEX_DIGITS[(unit >> 12) as usize],
+                        HEX_DIGITS[(unit >> 8 & 0xF) as usize],
+                        HEX_DIGITS[(unit >> 4 & 0xF) as usize],
+                        HEX_DIGITS[(unit & 0xF) as usize]]));
+    Ok(())
+}
+
 const BB: u8 = b'b';  // \x08
 const TT: u8 = b't';  // \x09
 const NN: u8 = b'n';  // \x0A
@@ -948,6 +1282,48 @@ fn fmt_f64_or_null<W>(wr: &mut W, value: f64) -> Result<()>
     Ok(())
 }
 
+fn fmt_f64_with<W>(wr: &mut W, value: f64, format: FloatFormat) -> Result<()>
+    where W: io::Write,
+{
+    if !value.is_finite() {
+        return fmt_f64_or_null(wr, value);
+    }
+    match format {
+        FloatFormat::Shortest => fmt_f64_or_null(wr, value),
+        FloatFormat::FixedPrecision(digits) => {
+            try!(write!(wr, "{:.*}", digits, value));
+            Ok(())
+        }
+        FloatFormat::PreserveInteger => {
+            if is_exact_integer(value) {
+                try!(itoa::write(wr, value as i64));
+                Ok(())
+            } else {
+                fmt_f64_or_null(wr, value)
+            }
+        }
+    }
+}
+
+// Every integer up to 2^53 in magnitude is exactly representable as an f64.
+fn is_exact_integer(value: f64) -> bool {
+    value.fract() == 0.0 && value.abs() <= 9007199254740992.0
+}
+
+fn fmt_non_finite<W>(wr: &mut W, value: f64) -> Result<()>
+    where W: io::Write,
+{
+    if value.is_nan() {
+        try!(wr.write_all(b"NaN"));
+    } else if value > 0.0 {
+        try!(wr.write_all(b"Infinity"));
+    } else {
+        try!(wr.write_all(b"-Infinity"));
+    }
+
+    Ok(())
+}
+
 /// Encode the specified struct into a json `[u8]` writer.
 #[inline]
 pub fn to_writer<W: ?Sized, T>(writer: &mut W, value: &T) -> Result<()>
@@ -970,6 +1346,46 @@ pub fn to_writer_pretty<W: ?Sized, T>(writer: &mut W, value: &T) -> Result<()>
     Ok(())
 }
 
+/// Encode each value as compact JSON on its own line, producing
+/// newline-delimited JSON that `from_ndjson` can read back.
+///
+/// Each value takes exactly one line. Newlines inside strings are always
+/// escaped, and the only other place a line break can appear is the
+/// whitespace inside a `RawValue`, which is written here with each line break
+/// turned into a space.
+pub fn to_ndjson<W: ?Sized, I>(writer: &mut W, values: I) -> Result<()>
+    where W: io::Write,
+          I: IntoIterator,
+          I::Item: ser::Serialize,
+{
+    for value in values {
+        let mut serializer = Serializer::new(SingleLine(&mut *writer));
+        try!(ser::Serialize::serialize(&value, &mut serializer));
+        try!(writer.write_all(b"\n"));
+    }
+    Ok(())
+}
+
+// Writes compact JSON with every line break turned into a space. A line
+// break in compact JSON can only be whitespace inside a `RawValue`.
+struct SingleLine<'a, W: ?Sized + 'a>(&'a mut W);
+
+impl<'a, W: ?Sized> io::Write for SingleLine<'a, W>
+    where W: io::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match buf.iter().position(|&b| b == b'\n' || b == b'\r') {
+            Some(0) => self.0.write_all(b" ").map(|()| 1),
+            Some(n) => self.0.write(&buf[..n]),
+            None => self.0.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
 /// Encode the specified struct into a json `[u8]` buffer.
 #[inline]
 pub fn to_vec<T>(value: &T) -> Result<Vec<u8>>
@@ -1020,6 +1436,29 @@ pub fn to_string_pretty<T>(value: &T) -> Result<String>
     Ok(string)
 }
 
+/// Encode a `Value` as pretty-printed JSON if it has fewer than
+/// `threshold_nodes` nodes, as counted by `Value::node_count`, and as compact
+/// JSON otherwise.
+///
+/// This keeps small documents readable while large ones stay on one line,
+/// for example in a log.
+///
+/// ```rust
+/// # use serde_json::Value;
+/// let value: Value = serde_json::from_str("[1, 2]").unwrap();
+/// let pretty = serde_json::to_string_auto(&value, 4).unwrap();
+/// assert_eq!(pretty, "[\n  1,\n  2\n]");
+/// let compact = serde_json::to_string_auto(&value, 3).unwrap();
+/// assert_eq!(compact, "[1,2]");
+/// ```
+pub fn to_string_auto(value: &Value, threshold_nodes: usize) -> Result<String> {
+    if value.node_count() < threshold_nodes {
+        to_string_pretty(value)
+    } else {
+        to_string(value)
+    }
+}
+
 fn indent<W>(wr: &mut W, n: usize, s: &[u8]) -> Result<()>
     where W: io::Write,
 {
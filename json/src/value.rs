@@ -38,8 +38,15 @@ use std::collections::{BTreeMap, btree_map};
 #[cfg(feature = "preserve_order")]
 use linked_hash_map::{self, LinkedHashMap};
 
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::error;
 use std::fmt;
 use std::io;
+use std::mem;
+#[cfg(feature = "arbitrary_precision")]
+use std::f64;
 use std::str;
 use std::vec;
 
@@ -48,12 +55,27 @@ use num_traits::NumCast;
 use serde::de;
 use serde::ser;
 
+use descendants;
 use error::{Error, ErrorCode};
+use ser::{CompactFormatter, SerializeOptions};
+#[cfg(feature = "arbitrary_precision")]
+use number::{self, MapKey};
+use pointer::{self, PointerError, PointerSetError};
 
 /// Represents a key/value type.
+///
+/// Keys are kept sorted, so an object's members are iterated and serialized
+/// in key order no matter what order they were inserted in. With the
+/// `preserve_order` feature they are kept in insertion order instead, and a
+/// newly inserted key goes at the end; replacing the value of an existing key
+/// leaves it in place.
 #[cfg(not(feature = "preserve_order"))]
 pub type Map<K, V> = BTreeMap<K, V>;
 /// Represents a key/value type.
+///
+/// Members are kept in insertion order, and a newly inserted key goes at the
+/// end; replacing the value of an existing key leaves it in place. Without
+/// the `preserve_order` feature keys are kept sorted instead.
 #[cfg(feature = "preserve_order")]
 pub type Map<K, V> = LinkedHashMap<K, V>;
 
@@ -70,7 +92,30 @@ type MapVisitor<K, T> = de::impls::BTreeMapVisitor<K, T>;
 type MapVisitor<K, T> = linked_hash_map::serde::LinkedHashMapVisitor<K, T>;
 
 /// Represents a JSON value
-#[derive(Clone, PartialEq)]
+///
+/// `Value` is `Clone` and `PartialEq`, and a clone is always equal to the
+/// original unless it contains a `NaN`. It does not implement `Eq` because
+/// `F64(NaN)` is not equal to itself, and so neither is any array or object
+/// containing it.
+///
+/// When parsing, a number with no fraction or exponent becomes `U64` if it is
+/// not negative and fits in a `u64`, and `I64` if it is negative and fits in
+/// an `i64`; `-0` becomes `I64(0)`. Any other number becomes `F64`, rounded
+/// to the nearest representable value, so integers beyond `u64::MAX` or
+/// below `i64::MIN`, and decimals with more digits than an `f64` holds, lose
+/// precision. With the `arbitrary_precision` feature such integers, and any
+/// other number that would not be written back with the same value, become
+/// `BigNumber` instead and keep every digit. A number too large for even an
+/// `f64` is an error.
+///
+/// ```rust
+/// # use serde_json::Value;
+/// let parse = |s| serde_json::from_str::<Value>(s).unwrap();
+/// assert_eq!(parse("18446744073709551615"), Value::U64(u64::max_value()));
+/// assert_eq!(parse("-9223372036854775808"), Value::I64(i64::min_value()));
+/// assert_eq!(parse("1.0"), Value::F64(1.0));
+/// ```
+#[derive(Clone)]
 pub enum Value {
     /// Represents a JSON null value
     Null,
@@ -87,6 +132,11 @@ pub enum Value {
     /// Represents a JSON floating point number
     F64(f64),
 
+    /// Represents a JSON number that would lose precision as an `i64`, `u64`
+    /// or `f64`, stored as the exact text it was written with.
+    #[cfg(feature = "arbitrary_precision")]
+    BigNumber(String),
+
     /// Represents a JSON string
     String(String),
 
@@ -98,6 +148,122 @@ pub enum Value {
 }
 
 impl Value {
+    /// Creates an empty Object.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut value = Value::object();
+    /// value.insert("a", Value::U64(1)).unwrap();
+    /// assert_eq!(value.to_string(), r#"{"a":1}"#);
+    /// ```
+    pub fn object() -> Value {
+        Value::Object(Map::new())
+    }
+
+    /// Creates an empty Object with room for `capacity` members. The
+    /// capacity is only used with the `preserve_order` feature, since a
+    /// `BTreeMap` does not preallocate.
+    pub fn object_with_capacity(capacity: usize) -> Value {
+        Value::Object(map_with_capacity(capacity))
+    }
+
+    /// Creates an empty Array.
+    pub fn array() -> Value {
+        Value::Array(Vec::new())
+    }
+
+    /// Index into an array by position or an object by key, returning None
+    /// if the index is missing or the `Value` is the wrong kind of container.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let value: Value = serde_json::from_str(r#"{"a": [true]}"#).unwrap();
+    /// assert_eq!(value.get("a").and_then(|a| a.get(0)), Some(&Value::Bool(true)));
+    /// assert_eq!(value.get(0), None);
+    /// ```
+    pub fn get<I: Index>(&self, index: I) -> Option<&Value> {
+        index.index_into(self)
+    }
+
+    /// Mutably index into an array by position or an object by key, returning
+    /// None if the index is missing or the `Value` is the wrong kind of
+    /// container.
+    pub fn get_mut<I: Index>(&mut self, index: I) -> Option<&mut Value> {
+        index.index_into_mut(self)
+    }
+
+    /// If the `Value` is an Object, returns the entry for `key` for in-place
+    /// manipulation. Returns None otherwise.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut counts: Value = serde_json::from_str("{}").unwrap();
+    /// for word in "a b a".split(' ') {
+    ///     counts.entry(word)
+    ///         .unwrap()
+    ///         .and_modify(|n| *n = Value::U64(n.as_u64().unwrap() + 1))
+    ///         .or_insert(Value::U64(1));
+    /// }
+    /// assert_eq!(counts.to_string(), r#"{"a":2,"b":1}"#);
+    /// ```
+    pub fn entry<S>(&mut self, key: S) -> Option<Entry>
+        where S: Into<String>,
+    {
+        match *self {
+            Value::Object(ref mut map) => Some(map_entry(map, key.into())),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is an Object, inserts `value` under `key` and returns
+    /// the value previously stored there, if any. Otherwise returns `value`
+    /// back as the error.
+    pub fn insert<S>(
+        &mut self,
+        key: S,
+        value: Value
+    ) -> Result<Option<Value>, Value>
+        where S: Into<String>,
+    {
+        match *self {
+            Value::Object(ref mut map) => Ok(map.insert(key.into(), value)),
+            _ => Err(value),
+        }
+    }
+
+    /// If the `Value` is an Array, appends `value` to it. Otherwise returns
+    /// `value` back as the error.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut list = Value::array();
+    /// list.push(Value::Bool(true)).unwrap();
+    /// assert_eq!(list.to_string(), "[true]");
+    /// assert_eq!(Value::Null.push(Value::U64(1)), Err(Value::U64(1)));
+    /// ```
+    pub fn push(&mut self, value: Value) -> Result<(), Value> {
+        match *self {
+            Value::Array(ref mut vec) => {
+                vec.push(value);
+                Ok(())
+            }
+            _ => Err(value),
+        }
+    }
+
+    /// Takes the value out of the `Value`, leaving a `Null` in its place.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut value: Value = serde_json::from_str(r#"{"x": [1]}"#).unwrap();
+    /// let x = value.get_mut("x").unwrap().take();
+    /// assert_eq!(x, Value::Array(vec![Value::U64(1)]));
+    /// assert_eq!(value.get("x"), Some(&Value::Null));
+    /// ```
+    pub fn take(&mut self) -> Value {
+        mem::replace(self, Value::Null)
+    }
+
     /// If the `Value` is an Object, returns the value associated with the provided key.
     /// Otherwise, returns None.
     pub fn find<'a>(&'a self, key: &str) -> Option<&'a Value> {
@@ -159,14 +325,228 @@ impl Value {
     /// addressed value is returned and if there is no such value `None` is
     /// returned.
     ///
+    /// Every `/` starts a new token, even at the end of the pointer, and a
+    /// token may be empty. So `""` is the whole document, while `"/"` is the
+    /// member with the empty key `""` of the root object and `"/a/"` is the
+    /// member with the empty key inside `a`.
+    ///
+    /// A `~` that is not followed by `0` or `1` is kept as literal text. Use
+    /// `pointer_strict` to reject such pointers instead.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let value: Value = serde_json::from_str(r#"{"": 1, "a": {"": 2}}"#).unwrap();
+    /// assert_eq!(value.pointer("/"), Some(&Value::U64(1)));
+    /// assert_eq!(value.pointer("/a/"), Some(&Value::U64(2)));
+    /// ```
+    ///
     /// For more information read [RFC6901](https://tools.ietf.org/html/rfc6901).
     pub fn pointer<'a>(&'a self, pointer: &str) -> Option<&'a Value> {
-        fn parse_index(s: &str) -> Option<usize> {
-            if s.starts_with('+') || (s.starts_with('0') && s.len() != 1) {
+        if pointer == "" {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+        let mut target = self;
+        for escaped_token in pointer.split('/').skip(1) {
+            let token = unescape_token(escaped_token);
+            let target_opt = match *target {
+                Value::Object(ref map) => map.get(&token[..]),
+                Value::Array(ref list) => {
+                    parse_index(&token[..]).and_then(|x| list.get(x))
+                }
+                _ => return None,
+            };
+            if let Some(t) = target_opt {
+                target = t;
+            } else {
+                return None;
+            }
+        }
+        Some(target)
+    }
+
+    /// Looks up a value by the reference tokens of a JSON Pointer, already
+    /// split and unescaped as returned by `pointer::parse_pointer`.
+    ///
+    /// This lets a pointer that is resolved against many documents be parsed
+    /// only once. An empty slice refers to the whole value.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// use serde_json::pointer::parse_pointer;
+    ///
+    /// let tokens = parse_pointer("/a~1b/1").unwrap();
+    /// let value: Value = serde_json::from_str(r#"{"a/b": [1, 2]}"#).unwrap();
+    /// assert_eq!(value.pointer_tokens(&tokens), Some(&Value::U64(2)));
+    /// assert_eq!(value.pointer_tokens(&["a/b", "0"]), Some(&Value::U64(1)));
+    /// ```
+    pub fn pointer_tokens<'a, S>(&'a self, tokens: &[S]) -> Option<&'a Value>
+        where S: AsRef<str>,
+    {
+        let mut target = self;
+        for token in tokens {
+            let token = token.as_ref();
+            let target_opt = match *target {
+                Value::Object(ref map) => map.get(token),
+                Value::Array(ref list) => {
+                    parse_index(token).and_then(|x| list.get(x))
+                }
+                _ => return None,
+            };
+            if let Some(t) = target_opt {
+                target = t;
+            } else {
                 return None;
             }
-            s.parse().ok()
         }
+        Some(target)
+    }
+
+    /// Looks up a value by a JSON Pointer, rejecting malformed pointers.
+    ///
+    /// `pointer` treats a `~` that is not followed by `0` or `1` as literal
+    /// text, so `/a~2` finds the key `"a~2"`. This method follows RFC6901
+    /// strictly and returns `PointerError::InvalidEscape` for it instead, or
+    /// `PointerError::MissingLeadingSlash` for a non-empty pointer without a
+    /// leading `/`. A well-formed pointer that resolves to nothing gives
+    /// `Ok(None)`.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// use serde_json::pointer::PointerError;
+    ///
+    /// let value: Value = serde_json::from_str(r#"{"a~2": 1}"#).unwrap();
+    /// assert_eq!(value.pointer("/a~2"), Some(&Value::U64(1)));
+    /// assert_eq!(value.pointer_strict("/a~2"), Err(PointerError::InvalidEscape));
+    /// assert_eq!(value.pointer_strict("/b"), Ok(None));
+    /// ```
+    pub fn pointer_strict<'a>(
+        &'a self,
+        pointer: &str
+    ) -> Result<Option<&'a Value>, PointerError> {
+        let tokens = try!(pointer::parse_pointer(pointer));
+        Ok(self.pointer_tokens(&tokens))
+    }
+
+    /// Looks up every value matching a JSON Pointer in which a `*` token
+    /// stands for every element of an array or every value of an object, and
+    /// a `**` token stands for the value itself and everything nested in it,
+    /// at any depth.
+    ///
+    /// Other tokens are resolved as in `pointer`. Matches are returned in
+    /// document order without duplicates, and a pattern that matches nothing
+    /// gives an empty vector. `*` and `**` tokens are always wildcards, so
+    /// this cannot address an object key that is literally `*` or `**`; use
+    /// `pointer` for that.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let value: Value = serde_json::from_str(r#"{
+    ///     "items": [{"price": 1}, {"name": "x"}, {"price": 3}]
+    /// }"#).unwrap();
+    /// let prices = value.pointer_all("/items/*/price");
+    /// assert_eq!(prices, vec![&Value::U64(1), &Value::U64(3)]);
+    ///
+    /// let value: Value = serde_json::from_str(r#"{
+    ///     "id": 1, "child": {"id": 2, "list": [{"id": 3}]}
+    /// }"#).unwrap();
+    /// assert_eq!(value.pointer_all("/**/id").len(), 3);
+    /// ```
+    pub fn pointer_all<'a>(&'a self, pattern: &str) -> Vec<&'a Value> {
+        self.pointer_all_paths(pattern)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Like `pointer_all`, but returns each match together with the JSON
+    /// Pointer that addresses it, with wildcards replaced by the actual key
+    /// or index. The pointers are escaped and can be passed back to `pointer`
+    /// or `pointer_mut`.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let value: Value = serde_json::from_str(r#"{
+    ///     "items": [{"price": 1}, {"name": "x"}, {"price": 3}]
+    /// }"#).unwrap();
+    /// let prices = value.pointer_all_paths("/items/*/price");
+    /// assert_eq!(prices, vec![("/items/0/price".to_owned(), &Value::U64(1)),
+    ///                         ("/items/2/price".to_owned(), &Value::U64(3))]);
+    /// ```
+    pub fn pointer_all_paths<'a>(
+        &'a self,
+        pattern: &str
+    ) -> Vec<(String, &'a Value)> {
+        if pattern == "" {
+            return vec![(String::new(), self)];
+        }
+        if !pattern.starts_with('/') {
+            return Vec::new();
+        }
+        let mut matches = vec![(String::new(), self)];
+        for escaped_token in pattern.split('/').skip(1) {
+            let mut next = Vec::new();
+            if escaped_token == "*" {
+                for (path, target) in matches {
+                    match *target {
+                        Value::Object(ref map) => {
+                            for (key, value) in map.iter() {
+                                next.push((pointer::join(&path, key), value));
+                            }
+                        }
+                        Value::Array(ref list) => {
+                            for (i, value) in list.iter().enumerate() {
+                                let index = i.to_string();
+                                next.push((pointer::join(&path, &index),
+                                           value));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            } else if escaped_token == "**" {
+                // Where one match is nested in another, their subtrees
+                // overlap; each node is visited only once regardless.
+                let mut seen = HashSet::new();
+                for (path, target) in matches {
+                    descendants::collect(target, path, &mut seen, &mut next);
+                }
+            } else {
+                let token = unescape_token(escaped_token);
+                for (path, target) in matches {
+                    let target_opt = match *target {
+                        Value::Object(ref map) => map.get(&token[..]),
+                        Value::Array(ref list) => {
+                            parse_index(&token[..]).and_then(|x| list.get(x))
+                        }
+                        _ => None,
+                    };
+                    if let Some(value) = target_opt {
+                        next.push((pointer::join(&path, &token), value));
+                    }
+                }
+            }
+            matches = next;
+        }
+        matches
+    }
+
+    /// Looks up a value by a JSON Pointer, additionally allowing negative
+    /// array indices that count back from the end of the array, so `-1` is
+    /// the last element. This is an extension of RFC6901, which `pointer`
+    /// follows strictly.
+    ///
+    /// A negative index past the start of the array, or `-0`, gives `None`.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let value: Value = serde_json::from_str(r#"{"arr": [1, 2, 3]}"#).unwrap();
+    /// assert_eq!(value.pointer_ext("/arr/-1"), Some(&Value::U64(3)));
+    /// assert_eq!(value.pointer_ext("/arr/-4"), None);
+    /// ```
+    pub fn pointer_ext<'a>(&'a self, pointer: &str) -> Option<&'a Value> {
         if pointer == "" {
             return Some(self);
         }
@@ -175,195 +555,1778 @@ impl Value {
         }
         let mut target = self;
         for escaped_token in pointer.split('/').skip(1) {
-            let token = escaped_token.replace("~1", "/").replace("~0", "~");
+            let token = unescape_token(escaped_token);
             let target_opt = match *target {
                 Value::Object(ref map) => map.get(&token[..]),
+                Value::Array(ref list) => {
+                    parse_index_ext(&token[..], list.len())
+                        .and_then(|x| list.get(x))
+                }
+                _ => return None,
+            };
+            if let Some(t) = target_opt {
+                target = t;
+            } else {
+                return None;
+            }
+        }
+        Some(target)
+    }
+
+    /// Looks up a value by a JSON Pointer, matching object keys without
+    /// regard to ASCII case. Array indices and escapes work as in `pointer`.
+    ///
+    /// A key that matches exactly is preferred. Otherwise, if several keys
+    /// differ from the token only in case, the first one in the object's
+    /// iteration order is used: alphabetical order by default, or insertion
+    /// order with the `preserve_order` feature.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let value: Value = serde_json::from_str(r#"{"foo": {"Bar": 1}}"#).unwrap();
+    /// assert_eq!(value.pointer_ci("/FOO/bar"), Some(&Value::U64(1)));
+    /// assert_eq!(value.pointer("/FOO/bar"), None);
+    /// ```
+    pub fn pointer_ci<'a>(&'a self, pointer: &str) -> Option<&'a Value> {
+        if pointer == "" {
+            return Some(self);
+        }
+        if !pointer.starts_with('/') {
+            return None;
+        }
+        let mut target = self;
+        for escaped_token in pointer.split('/').skip(1) {
+            let token = unescape_token(escaped_token);
+            let target_opt = match *target {
+                Value::Object(ref map) => {
+                    map.get(&token[..]).or_else(|| {
+                        map.iter()
+                            .find(|&(k, _)| k.eq_ignore_ascii_case(&token))
+                            .map(|(_, v)| v)
+                    })
+                }
                 Value::Array(ref list) => {
                     parse_index(&token[..]).and_then(|x| list.get(x))
                 }
-                _ => return None,
-            };
-            if let Some(t) = target_opt {
-                target = t;
-            } else {
-                return None;
+                _ => return None,
+            };
+            if let Some(t) = target_opt {
+                target = t;
+            } else {
+                return None;
+            }
+        }
+        Some(target)
+    }
+
+    /// Looks up a value by a JSON Pointer and returns an owned copy of it,
+    /// leaving the document unchanged. Returns `None` if there is no such
+    /// value.
+    pub fn pointer_clone(&self, pointer: &str) -> Option<Value> {
+        self.pointer(pointer).cloned()
+    }
+
+    /// Looks up a value by a JSON Pointer, returning `default` if there is no
+    /// such value.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let config: Value = serde_json::from_str(r#"{"port": 80}"#).unwrap();
+    /// let default = Value::String("localhost".to_owned());
+    /// assert_eq!(config.pointer_or("/port", &default), &Value::U64(80));
+    /// assert_eq!(config.pointer_or("/host", &default), &default);
+    /// ```
+    pub fn pointer_or<'a>(
+        &'a self,
+        pointer: &str,
+        default: &'a Value
+    ) -> &'a Value {
+        self.pointer(pointer).unwrap_or(default)
+    }
+
+    /// Looks up a value by a JSON Pointer and returns a mutable reference to
+    /// that value.
+    ///
+    /// See `pointer` for the pointer syntax. Returns `None` if there is no
+    /// such value.
+    ///
+    /// As in JSON Patch, a last reference token of `-` on an array refers to
+    /// the position past its end: a `Null` is pushed onto the array and a
+    /// reference to it is returned, so that it can be set to the value being
+    /// appended. A `-` anywhere else in the pointer refers to nothing.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut value: Value = serde_json::from_str(r#"{"x": [1, 2]}"#).unwrap();
+    /// *value.pointer_mut("/x/1").unwrap() = Value::U64(3);
+    /// *value.pointer_mut("/x/-").unwrap() = Value::U64(4);
+    /// assert_eq!(value.to_string(), r#"{"x":[1,3,4]}"#);
+    /// ```
+    pub fn pointer_mut<'a>(
+        &'a mut self,
+        pointer: &str
+    ) -> Option<&'a mut Value> {
+        lookup_mut(self, pointer, true)
+    }
+
+    /// Replaces the value a JSON Pointer refers to, returning true, or returns
+    /// false and leaves the document unchanged if there is no such value.
+    ///
+    /// See `pointer` for the pointer syntax. Only existing values are
+    /// replaced, except that a pointer ending in `-` appends to an array as
+    /// it does for `pointer_mut`.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut value: Value = serde_json::from_str(r#"{"x": [1, 2]}"#).unwrap();
+    /// assert!(value.pointer_set("/x/1", Value::U64(3)));
+    /// assert!(value.pointer_set("/x/-", Value::U64(4)));
+    /// assert!(!value.pointer_set("/y", Value::U64(5)));
+    /// assert_eq!(value.to_string(), r#"{"x":[1,3,4]}"#);
+    /// ```
+    pub fn pointer_set(&mut self, pointer: &str, value: Value) -> bool {
+        match self.pointer_mut(pointer) {
+            Some(target) => {
+                *target = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Looks up the array or object containing the value a JSON Pointer
+    /// refers to, returning a mutable reference to it together with the
+    /// unescaped last reference token.
+    ///
+    /// The value itself need not exist, so this can be used to insert as well
+    /// as to remove. Returns `None` for the empty pointer, which has no
+    /// parent, and if the parent does not exist or is not an array or object.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut value: Value = serde_json::from_str(r#"{"a": {"b": 1}}"#).unwrap();
+    /// {
+    ///     let (parent, key) = value.pointer_parent_mut("/a/b").unwrap();
+    ///     parent.as_object_mut().unwrap().remove(&key);
+    /// }
+    /// assert_eq!(value.to_string(), r#"{"a":{}}"#);
+    /// ```
+    pub fn pointer_parent_mut<'a>(
+        &'a mut self,
+        pointer: &str
+    ) -> Option<(&'a mut Value, String)> {
+        let parent = match pointer::parent(pointer) {
+            Some(parent) => parent,
+            None => return None,
+        };
+        let token = unescape_token(&pointer[parent.len() + 1..]).into_owned();
+        match lookup_mut(self, parent, false) {
+            Some(target) => {
+                if target.is_object() || target.is_array() {
+                    Some((target, token))
+                } else {
+                    None
+                }
+            }
+            None => None,
+        }
+    }
+
+    /// Looks up several values by JSON Pointer at once, returning a mutable
+    /// reference to each so that they can be modified together, for example
+    /// to swap them.
+    ///
+    /// The result has one entry per pointer, in the same order, which is
+    /// `None` if the pointer is malformed or there is no such value. Returns
+    /// `PointerSetError::Conflict` with the later pointer if two pointers are
+    /// equal or one addresses a value inside the other's, since the
+    /// references would alias.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// use std::mem;
+    ///
+    /// let mut value: Value = serde_json::from_str(r#"{"a": 1, "b": 2}"#).unwrap();
+    /// {
+    ///     let mut refs = value.pointer_mut_many(&["/a", "/b"]).unwrap();
+    ///     let b = refs.pop().unwrap().unwrap();
+    ///     let a = refs.pop().unwrap().unwrap();
+    ///     mem::swap(a, b);
+    /// }
+    /// assert_eq!(value.to_string(), r#"{"a":2,"b":1}"#);
+    /// ```
+    pub fn pointer_mut_many<'a>(
+        &'a mut self,
+        pointers: &[&str]
+    ) -> Result<Vec<Option<&'a mut Value>>, PointerSetError> {
+        let mut paths: Vec<(usize, Vec<String>)> = Vec::new();
+        for (i, p) in pointers.iter().enumerate() {
+            let tokens = match pointer::parse_pointer(p) {
+                Ok(tokens) => tokens,
+                Err(_) => continue,
+            };
+            for &(_, ref other) in &paths {
+                let n = tokens.len().min(other.len());
+                if tokens[..n] == other[..n] {
+                    return Err(PointerSetError::Conflict((*p).to_owned()));
+                }
+            }
+            paths.push((i, tokens));
+        }
+
+        let mut found = Vec::with_capacity(pointers.len());
+        for _ in pointers {
+            found.push(None);
+        }
+        let tails = paths.iter().map(|&(i, ref p)| (i, &p[..])).collect();
+        resolve_many(self, tails, &mut found);
+        Ok(found)
+    }
+
+    /// If the `Value` is an Object, performs a depth-first search until
+    /// a value associated with the provided key is found. If no value is found
+    /// or the `Value` is not an Object, returns None.
+    pub fn search<'a>(&'a self, key: &str) -> Option<&'a Value> {
+        match *self {
+            Value::Object(ref map) => {
+                match map.get(key) {
+                    Some(json_value) => Some(json_value),
+                    None => {
+                        for (_, v) in map.iter() {
+                            match v.search(key) {
+                                x if x.is_some() => return x,
+                                _ => (),
+                            }
+                        }
+                        None
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Builds a copy of the document that keeps only the values addressed by
+    /// `pointers` and the containers leading to them.
+    ///
+    /// Pointers that are malformed or address nothing are skipped. Kept
+    /// array elements stay in their original order, but elements that are not
+    /// kept are removed, so later elements move down to fill the gap. If no
+    /// pointer matches, the result is `Null`.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let value: Value = serde_json::from_str(r#"{"a": {"b": 1, "c": 2}, "d": 3}"#).unwrap();
+    /// let projected = value.project(&["/a/b", "/x"]);
+    /// assert_eq!(projected.to_string(), r#"{"a":{"b":1}}"#);
+    /// ```
+    pub fn project(&self, pointers: &[&str]) -> Value {
+        let paths: Vec<Vec<String>> = pointers.iter()
+            .filter_map(|p| pointer::parse_pointer(p).ok())
+            .collect();
+        let tails: Vec<&[String]> = paths.iter().map(|p| &p[..]).collect();
+        project_paths(self, &tails).unwrap_or(Value::Null)
+    }
+
+    /// Returns true if the `Value` is an Object. Returns false otherwise.
+    pub fn is_object(&self) -> bool {
+        self.as_object().is_some()
+    }
+
+    /// If the `Value` is an Object, returns the associated Map.
+    /// Returns None otherwise.
+    pub fn as_object(&self) -> Option<&Map<String, Value>> {
+        match *self {
+            Value::Object(ref map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is an Object, returns the associated mutable Map.
+    /// Returns None otherwise.
+    pub fn as_object_mut(&mut self) -> Option<&mut Map<String, Value>> {
+        match *self {
+            Value::Object(ref mut map) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is an Object, consumes it into a vector of its members
+    /// in the map's iteration order. Returns None otherwise.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let value: Value = serde_json::from_str(r#"{"b": 2, "a": 1}"#).unwrap();
+    /// let mut pairs = value.into_pairs().unwrap();
+    /// pairs.retain(|&(ref key, _)| key != "b");
+    /// assert_eq!(pairs, vec![("a".to_owned(), Value::U64(1))]);
+    /// ```
+    pub fn into_pairs(self) -> Option<Vec<(String, Value)>> {
+        match self {
+            Value::Object(map) => Some(map.into_iter().collect()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the `Value` is an Array. Returns false otherwise.
+    pub fn is_array(&self) -> bool {
+        self.as_array().is_some()
+    }
+
+    /// If the `Value` is an Array, returns the associated vector.
+    /// Returns None otherwise.
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match *self {
+            Value::Array(ref array) => Some(&*array),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is an Array, returns the associated mutable vector.
+    /// Returns None otherwise.
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match *self {
+            Value::Array(ref mut list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the `Value` is a String. Returns false otherwise.
+    pub fn is_string(&self) -> bool {
+        self.as_str().is_some()
+    }
+
+    /// If the `Value` is a String, returns the associated str.
+    /// Returns None otherwise.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of a String, or the JSON text of a number or boolean,
+    /// such as `42`, `1.5` or `true`. Returns None for Null, Arrays and
+    /// Objects.
+    ///
+    /// This is meant for tolerant reading of fields that are sometimes quoted
+    /// and sometimes not.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let quoted: Value = serde_json::from_str(r#""42""#).unwrap();
+    /// let bare: Value = serde_json::from_str("42").unwrap();
+    /// assert_eq!(quoted.coerce_str(), Some("42".to_owned()));
+    /// assert_eq!(bare.coerce_str(), Some("42".to_owned()));
+    /// ```
+    pub fn coerce_str(&self) -> Option<String> {
+        match *self {
+            Value::String(ref s) => Some(s.clone()),
+            Value::Null | Value::Array(_) | Value::Object(_) => None,
+            _ => Some(self.to_string()),
+        }
+    }
+
+    /// Returns true if the `Value` is a Number. Returns false otherwise.
+    pub fn is_number(&self) -> bool {
+        match *self {
+            Value::I64(_) | Value::U64(_) | Value::F64(_) => true,
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigNumber(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if the `Value` is a i64. Returns false otherwise.
+    pub fn is_i64(&self) -> bool {
+        match *self {
+            Value::I64(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if the `Value` is a u64. Returns false otherwise.
+    pub fn is_u64(&self) -> bool {
+        match *self {
+            Value::U64(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if the `Value` is a f64. Returns false otherwise.
+    ///
+    /// An integer that fits in a `u64` or `i64` never parses to an f64, so
+    /// this tells `1.0` apart from `1` even though the two are numerically
+    /// equal. With the default `FloatFormat::Shortest`, serialization keeps
+    /// the distinction by writing an f64 with a decimal point or exponent.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let integer: Value = serde_json::from_str("1").unwrap();
+    /// let float: Value = serde_json::from_str("1.0").unwrap();
+    /// assert!(!integer.is_f64());
+    /// assert!(float.is_f64());
+    /// ```
+    pub fn is_f64(&self) -> bool {
+        match *self {
+            Value::F64(_) => true,
+            _ => false,
+        }
+    }
+
+    /// If the `Value` is a number, return or cast it to a i64.
+    /// Returns None otherwise.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::I64(n) => Some(n),
+            Value::U64(n) => NumCast::from(n),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a number, return or cast it to a u64.
+    /// Returns None otherwise.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::I64(n) => NumCast::from(n),
+            Value::U64(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is a number, return or cast it to a f64.
+    /// Returns None otherwise.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::I64(n) => NumCast::from(n),
+            Value::U64(n) => NumCast::from(n),
+            Value::F64(n) => Some(n),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigNumber(ref s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// If the `Value` is an integer, return or cast it to a i128.
+    /// Returns None otherwise.
+    pub fn as_i128(&self) -> Option<i128> {
+        match *self {
+            Value::I64(n) => Some(n as i128),
+            Value::U64(n) => Some(n as i128),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigNumber(ref s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the `Value` is a Boolean. Returns false otherwise.
+    pub fn is_boolean(&self) -> bool {
+        self.as_bool().is_some()
+    }
+
+    /// If the `Value` is a Boolean, returns the associated bool.
+    /// Returns None otherwise.
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the `Value` is a Null. Returns false otherwise.
+    pub fn is_null(&self) -> bool {
+        self.as_null().is_some()
+    }
+
+    /// If the `Value` is a Null, returns ().
+    /// Returns None otherwise.
+    pub fn as_null(&self) -> Option<()> {
+        match *self {
+            Value::Null => Some(()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the `Value` is an empty Object, Array or String, or is
+    /// Null, since a null holds nothing. Returns false for numbers and
+    /// Booleans.
+    pub fn is_empty(&self) -> bool {
+        match *self {
+            Value::Null => true,
+            Value::String(ref s) => s.is_empty(),
+            Value::Array(ref vec) => vec.is_empty(),
+            Value::Object(ref map) => map.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Returns the number of elements in an Array, members in an Object or
+    /// bytes in a String, matching `str::len`. Returns None for Null,
+    /// numbers and Booleans.
+    pub fn len(&self) -> Option<usize> {
+        match *self {
+            Value::String(ref s) => Some(s.len()),
+            Value::Array(ref vec) => Some(vec.len()),
+            Value::Object(ref map) => Some(map.len()),
+            _ => None,
+        }
+    }
+
+    /// Converts the `Value` into a `String`, or returns a `TypeError` holding
+    /// the value if it is not a String.
+    pub fn try_into_string(self) -> Result<String, TypeError> {
+        match self {
+            Value::String(s) => Ok(s),
+            value => Err(TypeError::new("string", value)),
+        }
+    }
+
+    /// Converts the `Value` into a `bool`, or returns a `TypeError` holding
+    /// the value if it is not a Boolean.
+    pub fn try_into_bool(self) -> Result<bool, TypeError> {
+        match self.as_bool() {
+            Some(b) => Ok(b),
+            None => Err(TypeError::new("boolean", self)),
+        }
+    }
+
+    /// Converts the `Value` into an `i64` as `as_i64` would, or returns a
+    /// `TypeError` holding the value if that is not possible.
+    pub fn try_into_i64(self) -> Result<i64, TypeError> {
+        match self.as_i64() {
+            Some(n) => Ok(n),
+            None => Err(TypeError::new("i64", self)),
+        }
+    }
+
+    /// Converts the `Value` into a `u64` as `as_u64` would, or returns a
+    /// `TypeError` holding the value if that is not possible.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// assert_eq!(Value::U64(7).try_into_u64().unwrap(), 7);
+    /// let err = Value::String("7".to_owned()).try_into_u64().unwrap_err();
+    /// assert_eq!(err.to_string(), "invalid type: expected u64, found string");
+    /// ```
+    pub fn try_into_u64(self) -> Result<u64, TypeError> {
+        match self.as_u64() {
+            Some(n) => Ok(n),
+            None => Err(TypeError::new("u64", self)),
+        }
+    }
+
+    /// Converts the `Value` into an `f64` as `as_f64` would, or returns a
+    /// `TypeError` holding the value if that is not possible.
+    pub fn try_into_f64(self) -> Result<f64, TypeError> {
+        match self.as_f64() {
+            Some(n) => Ok(n),
+            None => Err(TypeError::new("f64", self)),
+        }
+    }
+
+    // The name used for this kind of value in error messages.
+    fn kind_name(&self) -> &'static str {
+        match *self {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::I64(_) => "i64",
+            Value::U64(_) => "u64",
+            Value::F64(_) => "f64",
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigNumber(_) => "big number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+    }
+
+    /// Collapses the tree into a map from JSON Pointer to each leaf value.
+    ///
+    /// Leaves are scalars and empty arrays or objects. Array elements are keyed
+    /// by their index, and a scalar document is a single entry under `""`.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let value: Value = serde_json::from_str(r#"{"a": {"b": [1, 2]}}"#).unwrap();
+    /// let flat = value.flatten();
+    /// assert_eq!(flat.keys().collect::<Vec<_>>(), vec!["/a/b/0", "/a/b/1"]);
+    /// ```
+    pub fn flatten(&self) -> Map<String, Value> {
+        let mut flat = Map::new();
+        flatten_into(self, String::new(), &mut flat);
+        flat
+    }
+
+    /// Rebuilds a document from a map of JSON Pointers to leaf values, the
+    /// inverse of `flatten`.
+    ///
+    /// Objects and arrays are created as needed. An object whose keys are
+    /// exactly the indices `0` to `n - 1` becomes an array, so an object that
+    /// really had those keys does not survive a round trip. An empty map
+    /// gives an empty object.
+    ///
+    /// Returns `PointerSetError::Invalid` for a malformed pointer, and
+    /// `PointerSetError::Conflict` if one pointer addresses a location inside
+    /// or equal to another's, such as `/a` and `/a/b`.
+    pub fn unflatten(
+        flat: &Map<String, Value>
+    ) -> Result<Value, PointerSetError> {
+        let mut root = FlatNode::Empty;
+        for (path, leaf) in flat.iter() {
+            let tokens = match pointer::parse_pointer(path) {
+                Ok(tokens) => tokens,
+                Err(err) => {
+                    return Err(PointerSetError::Invalid(path.clone(), err));
+                }
+            };
+            if !root.insert(&tokens, leaf.clone()) {
+                return Err(PointerSetError::Conflict(path.clone()));
+            }
+        }
+        Ok(root.into_value())
+    }
+
+    /// Compares two values for equality, ignoring the order of object keys.
+    /// Arrays are still compared element by element.
+    ///
+    /// This is the same as `==` unless the `preserve_order` feature is
+    /// enabled, in which case `==` also compares the order of object keys.
+    pub fn eq_unordered(&self, other: &Value) -> bool {
+        match (self, other) {
+            (&Value::Array(ref a), &Value::Array(ref b)) => {
+                a.len() == b.len() &&
+                a.iter().zip(b.iter()).all(|(x, y)| x.eq_unordered(y))
+            }
+            (&Value::Object(ref a), &Value::Object(ref b)) => {
+                a.len() == b.len() &&
+                a.iter().all(|(k, x)| {
+                    b.get(k).map_or(false, |y| x.eq_unordered(y))
+                })
+            }
+            _ => self == other,
+        }
+    }
+
+    /// Compares two values like `eq_unordered`, but treats the values at the
+    /// JSON Pointers in `ignore` as equal whatever they hold, including when
+    /// one side is missing them. This is useful for comparing documents that
+    /// contain timestamps or generated ids.
+    ///
+    /// Pointers are matched exactly as written, so they should use the
+    /// canonical `~0` and `~1` escapes. Ignoring an array element does not
+    /// ignore the array's length.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let a: Value = serde_json::from_str(r#"{"id": 1, "at": 100}"#).unwrap();
+    /// let b: Value = serde_json::from_str(r#"{"id": 1, "at": 200}"#).unwrap();
+    /// assert!(a.eq_ignoring(&b, &["/at"]));
+    /// assert!(!a.eq_ignoring(&b, &["/id"]));
+    /// ```
+    pub fn eq_ignoring(&self, other: &Value, ignore: &[&str]) -> bool {
+        eq_ignoring_at(self, other, "", ignore)
+    }
+
+    /// Returns a JSON Merge Patch (RFC 7386) that turns this value into
+    /// `target`.
+    ///
+    /// The patch is an object holding only the members that differ, with
+    /// `null` for each member that `target` removes. Nested objects are
+    /// diffed recursively, while arrays and scalars that differ are replaced
+    /// whole, as the RFC requires. If either value is not an object, the
+    /// patch is `target` itself.
+    ///
+    /// A merge patch cannot set a member to `null`, since `null` means
+    /// removal, so the patch only reproduces `target` exactly if no object in
+    /// it has a `null` member.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let from = r#"{"a": 1, "b": {"c": 2, "d": 3}}"#;
+    /// let from: Value = serde_json::from_str(from).unwrap();
+    /// let to = r#"{"a": 1, "b": {"c": 4}, "e": [5]}"#;
+    /// let to: Value = serde_json::from_str(to).unwrap();
+    /// let patch = from.merge_diff(&to);
+    /// assert_eq!(patch.to_string(), r#"{"b":{"c":4,"d":null},"e":[5]}"#);
+    /// ```
+    pub fn merge_diff(&self, target: &Value) -> Value {
+        let (from, to) = match (self, target) {
+            (&Value::Object(ref from), &Value::Object(ref to)) => (from, to),
+            _ => return target.clone(),
+        };
+        let mut patch = Map::new();
+        for (key, value) in to {
+            match from.get(key) {
+                Some(old) if old.eq_unordered(value) => {}
+                Some(old) => {
+                    patch.insert(key.clone(), old.merge_diff(value));
+                }
+                None => {
+                    patch.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        for key in from.keys() {
+            if !to.contains_key(key) {
+                patch.insert(key.clone(), Value::Null);
+            }
+        }
+        Value::Object(patch)
+    }
+
+    /// Returns a 64-bit hash of the value's content, for deduplication.
+    ///
+    /// Values that are equal according to `eq_unordered` hash the same, so
+    /// the order of object keys does not matter, `I64(1)` and `U64(1)` hash
+    /// the same, and so do `F64(0.0)` and `F64(-0.0)`. An integer and an
+    /// `F64` are never equal, so `1` and `1.0` hash differently. Every `NaN`
+    /// hashes the same.
+    ///
+    /// The hash is FNV-1a over a fixed encoding of the value, so it is the
+    /// same on every run, platform and version of this crate. It is not
+    /// resistant to deliberately constructed collisions.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let a: Value = serde_json::from_str(r#"{"x": 1, "y": [2]}"#).unwrap();
+    /// let b: Value = serde_json::from_str(r#"{"y": [2], "x": 1}"#).unwrap();
+    /// assert_eq!(a.content_hash(), b.content_hash());
+    /// assert!(Value::U64(1).content_hash() != Value::F64(1.0).content_hash());
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher(0xcbf29ce484222325);
+        hasher.value(self);
+        hasher.0
+    }
+
+    /// Returns the maximum nesting depth of arrays and objects. Scalars have
+    /// depth 0 and an empty array or object has depth 1.
+    pub fn depth(&self) -> usize {
+        match *self {
+            Value::Array(ref vec) => {
+                1 + vec.iter().map(Value::depth).max().unwrap_or(0)
+            }
+            Value::Object(ref map) => {
+                1 + map.values().map(Value::depth).max().unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Returns the total number of values in the tree, counting this value,
+    /// every container and every scalar. Object keys are not counted.
+    pub fn node_count(&self) -> usize {
+        match *self {
+            Value::Array(ref vec) => {
+                1 + vec.iter().map(Value::node_count).sum::<usize>()
+            }
+            Value::Object(ref map) => {
+                1 + map.values().map(Value::node_count).sum::<usize>()
+            }
+            _ => 1,
+        }
+    }
+
+    /// Returns the total length in bytes of every string in the tree,
+    /// including object keys.
+    pub fn string_bytes(&self) -> usize {
+        match *self {
+            Value::String(ref s) => s.len(),
+            Value::Array(ref vec) => vec.iter().map(Value::string_bytes).sum(),
+            Value::Object(ref map) => {
+                map.iter().map(|(k, v)| k.len() + v.string_bytes()).sum()
+            }
+            _ => 0,
+        }
+    }
+
+    /// Returns an estimate of the memory used by this value in bytes: the
+    /// `Value` itself plus the heap allocations of its strings, arrays and
+    /// objects, recursively.
+    ///
+    /// String and array capacities are counted in full. Objects are counted
+    /// as one key and one value per member; the map's own node overhead is
+    /// not included, so the estimate is a lower bound.
+    pub fn deep_size(&self) -> usize {
+        mem::size_of::<Value>() + self.heap_size()
+    }
+
+    fn heap_size(&self) -> usize {
+        match *self {
+            Value::String(ref s) => s.capacity(),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigNumber(ref s) => s.capacity(),
+            Value::Array(ref vec) => {
+                vec.capacity() * mem::size_of::<Value>() +
+                vec.iter().map(Value::heap_size).sum::<usize>()
+            }
+            Value::Object(ref map) => {
+                map.iter()
+                    .map(|(k, v)| {
+                        mem::size_of::<String>() + k.capacity() +
+                        mem::size_of::<Value>() + v.heap_size()
+                    })
+                    .sum()
+            }
+            _ => 0,
+        }
+    }
+
+    /// If the `Value` is an Object, removes every member for which `f`
+    /// returns false. Does nothing otherwise.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut value: Value = serde_json::from_str(r#"{"a": 1, "b": null}"#).unwrap();
+    /// value.retain(|_, v| !v.is_null());
+    /// assert_eq!(value.to_string(), r#"{"a":1}"#);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F)
+        where F: FnMut(&str, &Value) -> bool,
+    {
+        if let Value::Object(ref mut map) = *self {
+            let removed: Vec<String> = map.iter()
+                .filter(|&(k, v)| !f(k, v))
+                .map(|(k, _)| k.clone())
+                .collect();
+            for key in removed {
+                map.remove(&key);
+            }
+        }
+    }
+
+    /// If the `Value` is an Array, removes every element for which `f`
+    /// returns false. `f` is given each element's original index. Does nothing
+    /// otherwise.
+    pub fn retain_elements<F>(&mut self, mut f: F)
+        where F: FnMut(usize, &Value) -> bool,
+    {
+        if let Value::Array(ref mut vec) = *self {
+            let mut index = 0;
+            vec.retain(|v| {
+                index += 1;
+                f(index - 1, v)
+            });
+        }
+    }
+
+    /// Recursively removes every object member whose value is Null. If
+    /// `drop_array_nulls` is true, Null elements are removed from arrays as
+    /// well; otherwise they are kept, so that the other elements keep their
+    /// positions.
+    ///
+    /// Objects and arrays that end up empty are kept.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut value: Value = serde_json::from_str(
+    ///     r#"{"a": null, "b": {"c": null, "d": [1, null]}}"#).unwrap();
+    /// value.prune_nulls(false);
+    /// assert_eq!(value.to_string(), r#"{"b":{"d":[1,null]}}"#);
+    /// value.prune_nulls(true);
+    /// assert_eq!(value.to_string(), r#"{"b":{"d":[1]}}"#);
+    /// ```
+    pub fn prune_nulls(&mut self, drop_array_nulls: bool) {
+        match *self {
+            Value::Array(ref mut vec) => {
+                if drop_array_nulls {
+                    vec.retain(|v| !v.is_null());
+                }
+                for element in vec.iter_mut() {
+                    element.prune_nulls(drop_array_nulls);
+                }
+            }
+            Value::Object(_) => {
+                self.retain(|_, v| !v.is_null());
+                if let Value::Object(ref mut map) = *self {
+                    for (_, element) in map.iter_mut() {
+                        element.prune_nulls(drop_array_nulls);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Recursively sorts the keys of every object in the tree.
+    ///
+    /// With the default `BTreeMap` representation keys are always kept in
+    /// sorted order, so this does nothing. It is useful with the
+    /// `preserve_order` feature, where keys otherwise stay in insertion order.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut value: Value = serde_json::from_str(r#"{"b": {"d": 1, "c": 2}, "a": 3}"#).unwrap();
+    /// value.sort_keys();
+    /// assert_eq!(value.to_string(), r#"{"a":3,"b":{"c":2,"d":1}}"#);
+    /// ```
+    pub fn sort_keys(&mut self) {
+        match *self {
+            Value::Array(ref mut vec) => {
+                for element in vec.iter_mut() {
+                    element.sort_keys();
+                }
+            }
+            Value::Object(ref mut map) => {
+                for (_, element) in map.iter_mut() {
+                    element.sort_keys();
+                }
+                sort_map(map);
+            }
+            _ => {}
+        }
+    }
+
+    /// Calls `f` on every scalar in the tree, that is every value that is not
+    /// an array or object, allowing it to be rewritten in place. Empty arrays
+    /// and objects are not passed to `f`.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut value: Value = serde_json::from_str(r#"[1, [2, 3]]"#).unwrap();
+    /// value.map_scalars(|v| *v = Value::U64(v.as_u64().unwrap() * 10));
+    /// assert_eq!(serde_json::to_string(&value).unwrap(), "[10,[20,30]]");
+    /// ```
+    pub fn map_scalars<F>(&mut self, mut f: F)
+        where F: FnMut(&mut Value),
+    {
+        map_scalars_with(self, &mut f);
+    }
+
+    /// Traverses the tree depth first in document order, calling `visitor`
+    /// on entering and leaving every array and object and on every scalar.
+    /// Each call is given the JSON Pointer of the value it is about.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// use serde_json::value::Visitor;
+    ///
+    /// struct Paths(Vec<String>);
+    ///
+    /// impl Visitor for Paths {
+    ///     fn scalar(&mut self, path: &str, _value: &Value) {
+    ///         self.0.push(path.to_owned());
+    ///     }
+    /// }
+    ///
+    /// let value: Value = serde_json::from_str(r#"{"a": [1, 2], "b": {}}"#).unwrap();
+    /// let mut paths = Paths(Vec::new());
+    /// value.walk(&mut paths);
+    /// assert_eq!(paths.0, vec!["/a/0", "/a/1"]);
+    /// ```
+    pub fn walk<V>(&self, visitor: &mut V)
+        where V: Visitor,
+    {
+        walk_with(self, "", visitor);
+    }
+
+    /// Compares two values using a total order, for sorting.
+    ///
+    /// Values are ordered first by kind, `Null < Bool < Number < String <
+    /// Array < Object`, then by content: numbers by numeric value regardless
+    /// of variant, strings by their bytes, and arrays and objects
+    /// lexicographically by their elements and `(key, value)` entries in
+    /// iteration order. An integer sorts before an `F64` with the same value,
+    /// and `NaN` sorts after every other number.
+    ///
+    /// `Value` only implements `PartialOrd`, which agrees with this order
+    /// except that it considers `NaN` unordered, so use this method with
+    /// `sort_by` to sort values that might contain `NaN`.
+    ///
+    /// ```rust
+    /// # use serde_json::Value;
+    /// let mut values = vec![Value::String("a".to_owned()), Value::F64(1.5),
+    ///                       Value::Null, Value::I64(-2), Value::Bool(true)];
+    /// values.sort_by(Value::total_cmp);
+    /// assert_eq!(values, vec![Value::Null, Value::Bool(true), Value::I64(-2),
+    ///                         Value::F64(1.5), Value::String("a".to_owned())]);
+    /// ```
+    pub fn total_cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (&Value::Array(ref a), &Value::Array(ref b)) => {
+                for (x, y) in a.iter().zip(b.iter()) {
+                    match x.total_cmp(y) {
+                        Ordering::Equal => {}
+                        ord => return ord,
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
+            (&Value::Object(ref a), &Value::Object(ref b)) => {
+                for ((ka, va), (kb, vb)) in a.iter().zip(b.iter()) {
+                    match ka.cmp(kb) {
+                        Ordering::Equal => {}
+                        ord => return ord,
+                    }
+                    match va.total_cmp(vb) {
+                        Ordering::Equal => {}
+                        ord => return ord,
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
+            _ => {
+                match self.partial_cmp_scalar(other) {
+                    Some(ord) => ord,
+                    // Only NaN is unordered.
+                    None => is_nan(self).cmp(&is_nan(other)),
+                }
+            }
+        }
+    }
+
+    fn partial_cmp_scalar(&self, other: &Value) -> Option<Ordering> {
+        let (a, b) = (kind_rank(self), kind_rank(other));
+        if a != b {
+            return Some(a.cmp(&b));
+        }
+        match (self, other) {
+            (&Value::Bool(a), &Value::Bool(b)) => Some(a.cmp(&b)),
+            (&Value::String(ref a), &Value::String(ref b)) => Some(a.cmp(b)),
+            _ if self.is_number() => compare_numbers(self, other),
+            _ => Some(Ordering::Equal),
+        }
+    }
+}
+
+fn resolve_many<'a>(
+    value: &'a mut Value,
+    paths: Vec<(usize, &[String])>,
+    found: &mut Vec<Option<&'a mut Value>>
+) {
+    if paths.is_empty() {
+        return;
+    }
+    // Pointers never overlap, so an empty path is the only one left.
+    if paths[0].1.is_empty() {
+        found[paths[0].0] = Some(value);
+        return;
+    }
+    match *value {
+        Value::Object(ref mut map) => {
+            for (key, child) in map.iter_mut() {
+                let tails = paths.iter()
+                    .filter(|&&(_, path)| path[0] == *key)
+                    .map(|&(i, path)| (i, &path[1..]))
+                    .collect();
+                resolve_many(child, tails, found);
+            }
+        }
+        Value::Array(ref mut vec) => {
+            for (index, child) in vec.iter_mut().enumerate() {
+                let tails = paths.iter()
+                    .filter(|&&(_, path)| parse_index(&path[0]) == Some(index))
+                    .map(|&(i, path)| (i, &path[1..]))
+                    .collect();
+                resolve_many(child, tails, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn project_paths(value: &Value, paths: &[&[String]]) -> Option<Value> {
+    if paths.is_empty() {
+        return None;
+    }
+    if paths.iter().any(|path| path.is_empty()) {
+        return Some(value.clone());
+    }
+    match *value {
+        Value::Object(ref map) => {
+            let mut projected = Map::new();
+            for (key, child) in map.iter() {
+                let tails: Vec<&[String]> = paths.iter()
+                    .filter(|path| path[0] == *key)
+                    .map(|path| &path[1..])
+                    .collect();
+                if let Some(v) = project_paths(child, &tails) {
+                    projected.insert(key.clone(), v);
+                }
+            }
+            if projected.is_empty() {
+                None
+            } else {
+                Some(Value::Object(projected))
+            }
+        }
+        Value::Array(ref vec) => {
+            let mut projected = Vec::new();
+            for (index, child) in vec.iter().enumerate() {
+                let tails: Vec<&[String]> = paths.iter()
+                    .filter(|path| parse_index(&path[0]) == Some(index))
+                    .map(|path| &path[1..])
+                    .collect();
+                if let Some(v) = project_paths(child, &tails) {
+                    projected.push(v);
+                }
+            }
+            if projected.is_empty() {
+                None
+            } else {
+                Some(Value::Array(projected))
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "preserve_order"))]
+fn map_with_capacity(_capacity: usize) -> Map<String, Value> {
+    Map::new()
+}
+
+#[cfg(feature = "preserve_order")]
+fn map_with_capacity(capacity: usize) -> Map<String, Value> {
+    Map::with_capacity(capacity)
+}
+
+#[cfg(not(feature = "preserve_order"))]
+fn sort_map(_map: &mut Map<String, Value>) {}
+
+#[cfg(feature = "preserve_order")]
+fn sort_map(map: &mut Map<String, Value>) {
+    let mut entries: Vec<_> =
+        mem::replace(map, Map::new()).into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    for (key, value) in entries {
+        map.insert(key, value);
+    }
+}
+
+fn eq_ignoring_at(a: &Value, b: &Value, path: &str, ignore: &[&str]) -> bool {
+    if ignore.contains(&path) {
+        return true;
+    }
+    match (a, b) {
+        (&Value::Array(ref a), &Value::Array(ref b)) => {
+            a.len() == b.len() &&
+            a.iter().zip(b.iter()).enumerate().all(|(i, (x, y))| {
+                let child = pointer::join(path, &i.to_string());
+                eq_ignoring_at(x, y, &child, ignore)
+            })
+        }
+        (&Value::Object(ref a), &Value::Object(ref b)) => {
+            let ignored = |k: &str| {
+                ignore.contains(&&pointer::join(path, k)[..])
+            };
+            a.iter().all(|(k, x)| {
+                match b.get(k) {
+                    Some(y) => {
+                        let child = pointer::join(path, k);
+                        eq_ignoring_at(x, y, &child, ignore)
+                    }
+                    None => ignored(k),
+                }
+            }) &&
+            b.keys().all(|k| a.contains_key(k) || ignored(k))
+        }
+        _ => a == b,
+    }
+}
+
+fn map_scalars_with<F>(value: &mut Value, f: &mut F)
+    where F: FnMut(&mut Value),
+{
+    match *value {
+        Value::Array(ref mut vec) => {
+            for element in vec.iter_mut() {
+                map_scalars_with(element, f);
+            }
+        }
+        Value::Object(ref mut map) => {
+            for (_, element) in map.iter_mut() {
+                map_scalars_with(element, f);
+            }
+        }
+        _ => f(value),
+    }
+}
+
+// 64-bit FNV-1a, fed a tagged, length-prefixed encoding of a value so that
+// different values do not run together into the same bytes.
+struct ContentHasher(u64);
+
+impl ContentHasher {
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn u64(&mut self, n: u64) {
+        let mut buf = [0; 8];
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = (n >> (8 * i)) as u8;
+        }
+        self.bytes(&buf);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.u64(s.len() as u64);
+        self.bytes(s.as_bytes());
+    }
+
+    fn value(&mut self, value: &Value) {
+        match *value {
+            Value::Null => self.bytes(b"n"),
+            Value::Bool(b) => self.bytes(if b { b"t" } else { b"f" }),
+            Value::I64(n) if n < 0 => {
+                self.bytes(b"-");
+                self.u64(n as u64);
+            }
+            Value::I64(n) => {
+                self.bytes(b"+");
+                self.u64(n as u64);
+            }
+            Value::U64(n) => {
+                self.bytes(b"+");
+                self.u64(n);
+            }
+            Value::F64(n) => {
+                let n = if n.is_nan() {
+                    f64::NAN
+                } else if n == 0.0 {
+                    0.0
+                } else {
+                    n
+                };
+                self.bytes(b"d");
+                self.u64(n.to_bits());
+            }
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigNumber(ref n) => {
+                self.bytes(b"b");
+                self.str(n);
+            }
+            Value::String(ref s) => {
+                self.bytes(b"s");
+                self.str(s);
+            }
+            Value::Array(ref vec) => {
+                self.bytes(b"a");
+                self.u64(vec.len() as u64);
+                for element in vec {
+                    self.value(element);
+                }
+            }
+            Value::Object(ref map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                self.bytes(b"o");
+                self.u64(entries.len() as u64);
+                for (key, element) in entries {
+                    self.str(key);
+                    self.value(element);
+                }
+            }
+        }
+    }
+}
+
+// Resolves a JSON Pointer to a mutable reference. If `append` is true, a last
+// token of `-` on an array pushes a `Null` and refers to it.
+fn lookup_mut<'a>(
+    value: &'a mut Value,
+    pointer: &str,
+    append: bool
+) -> Option<&'a mut Value> {
+    if pointer == "" {
+        return Some(value);
+    }
+    if !pointer.starts_with('/') {
+        return None;
+    }
+    let mut target = value;
+    let mut tokens = pointer.split('/').skip(1).peekable();
+    while let Some(escaped_token) = tokens.next() {
+        let token = unescape_token(escaped_token);
+        let last = tokens.peek().is_none();
+        // Move the reference out so that it can be reborrowed for the
+        // next level without conflicting with the loop variable.
+        let target_once = target;
+        let target_opt = match *target_once {
+            Value::Object(ref mut map) => map.get_mut(&token[..]),
+            Value::Array(ref mut list) => {
+                if append && last && token == "-" {
+                    list.push(Value::Null);
+                    list.last_mut()
+                } else {
+                    parse_index(&token[..]).and_then(move |x| list.get_mut(x))
+                }
+            }
+            _ => return None,
+        };
+        if let Some(t) = target_opt {
+            target = t;
+        } else {
+            return None;
+        }
+    }
+    Some(target)
+}
+
+fn walk_with<V>(value: &Value, path: &str, visitor: &mut V)
+    where V: Visitor,
+{
+    match *value {
+        Value::Array(ref vec) => {
+            visitor.enter_array(path, vec);
+            for (i, element) in vec.iter().enumerate() {
+                let child = pointer::join(path, &i.to_string());
+                walk_with(element, &child, visitor);
+            }
+            visitor.leave_array(path, vec);
+        }
+        Value::Object(ref map) => {
+            visitor.enter_object(path, map);
+            for (key, element) in map.iter() {
+                walk_with(element, &pointer::join(path, key), visitor);
+            }
+            visitor.leave_object(path, map);
+        }
+        _ => visitor.scalar(path, value),
+    }
+}
+
+fn flatten_into(value: &Value, path: String, flat: &mut Map<String, Value>) {
+    match *value {
+        Value::Array(ref vec) if !vec.is_empty() => {
+            for (i, v) in vec.iter().enumerate() {
+                flatten_into(v, pointer::join(&path, &i.to_string()), flat);
+            }
+        }
+        Value::Object(ref map) if !map.is_empty() => {
+            for (k, v) in map.iter() {
+                flatten_into(v, pointer::join(&path, k), flat);
+            }
+        }
+        _ => {
+            flat.insert(path, value.clone());
+        }
+    }
+}
+
+enum FlatNode {
+    Empty,
+    Leaf(Value),
+    Branch(Map<String, FlatNode>),
+}
+
+impl FlatNode {
+    // Returns false if the location is already taken.
+    fn insert(&mut self, tokens: &[String], leaf: Value) -> bool {
+        let (token, rest) = match tokens.split_first() {
+            Some(split) => split,
+            None => {
+                if let FlatNode::Empty = *self {
+                    *self = FlatNode::Leaf(leaf);
+                    return true;
+                }
+                return false;
+            }
+        };
+        if let FlatNode::Empty = *self {
+            *self = FlatNode::Branch(Map::new());
+        }
+        match *self {
+            FlatNode::Branch(ref mut children) => {
+                if !children.contains_key(token) {
+                    children.insert(token.clone(), FlatNode::Empty);
+                }
+                children.get_mut(token).unwrap().insert(rest, leaf)
             }
+            _ => false,
         }
-        Some(target)
     }
 
-    /// If the `Value` is an Object, performs a depth-first search until
-    /// a value associated with the provided key is found. If no value is found
-    /// or the `Value` is not an Object, returns None.
-    pub fn search<'a>(&'a self, key: &str) -> Option<&'a Value> {
-        match *self {
-            Value::Object(ref map) => {
-                match map.get(key) {
-                    Some(json_value) => Some(json_value),
-                    None => {
-                        for (_, v) in map.iter() {
-                            match v.search(key) {
-                                x if x.is_some() => return x,
-                                _ => (),
-                            }
-                        }
-                        None
-                    }
+    fn into_value(self) -> Value {
+        match self {
+            FlatNode::Empty => Value::Object(Map::new()),
+            FlatNode::Leaf(value) => value,
+            FlatNode::Branch(children) => {
+                let len = children.len();
+                let is_array = children.keys()
+                    .all(|k| parse_index(k).map_or(false, |i| i < len));
+                if is_array {
+                    let mut elements: Vec<_> = children.into_iter()
+                        .map(|(k, v)| (parse_index(&k).unwrap(), v))
+                        .collect();
+                    elements.sort_by_key(|&(i, _)| i);
+                    Value::Array(elements.into_iter()
+                        .map(|(_, v)| v.into_value())
+                        .collect())
+                } else {
+                    Value::Object(children.into_iter()
+                        .map(|(k, v)| (k, v.into_value()))
+                        .collect())
                 }
             }
-            _ => None,
         }
     }
+}
 
-    /// Returns true if the `Value` is an Object. Returns false otherwise.
-    pub fn is_object(&self) -> bool {
-        self.as_object().is_some()
+// Undoes the `~1` and `~0` escapes in a pointer reference token. Most tokens
+// contain no `~` at all, so those are borrowed rather than copied.
+fn unescape_token(escaped: &str) -> Cow<str> {
+    if escaped.contains('~') {
+        Cow::Owned(escaped.replace("~1", "/").replace("~0", "~"))
+    } else {
+        Cow::Borrowed(escaped)
     }
+}
 
-    /// If the `Value` is an Object, returns the associated Map.
-    /// Returns None otherwise.
-    pub fn as_object(&self) -> Option<&Map<String, Value>> {
-        match *self {
-            Value::Object(ref map) => Some(map),
-            _ => None,
-        }
+fn parse_index(s: &str) -> Option<usize> {
+    if s.starts_with('+') || (s.starts_with('0') && s.len() != 1) {
+        return None;
     }
+    s.parse().ok()
+}
 
-    /// If the `Value` is an Object, returns the associated mutable Map.
-    /// Returns None otherwise.
-    pub fn as_object_mut(&mut self) -> Option<&mut Map<String, Value>> {
-        match *self {
-            Value::Object(ref mut map) => Some(map),
+// Like `parse_index`, but `-n` counts back from the end of an array of length
+// `len`.
+fn parse_index_ext(s: &str, len: usize) -> Option<usize> {
+    if s.starts_with('-') {
+        match parse_index(&s[1..]) {
+            Some(n) if n >= 1 && n <= len => Some(len - n),
             _ => None,
         }
+    } else {
+        parse_index(s)
     }
+}
 
-    /// Returns true if the `Value` is an Array. Returns false otherwise.
-    pub fn is_array(&self) -> bool {
-        self.as_array().is_some()
+/// A single key of a JSON object, which may be present or absent. Returned by
+/// `Value::entry`.
+pub enum Entry<'a> {
+    /// The key is present; holds its value.
+    Occupied(&'a mut Value),
+
+    /// The key is absent.
+    Vacant(VacantEntry<'a>),
+}
+
+impl<'a> Entry<'a> {
+    /// Inserts `default` if the key is absent, then returns the key's value.
+    pub fn or_insert(self, default: Value) -> &'a mut Value {
+        match self {
+            Entry::Occupied(value) => value,
+            Entry::Vacant(entry) => entry.insert(default),
+        }
     }
 
-    /// If the `Value` is an Array, returns the associated vector.
-    /// Returns None otherwise.
-    pub fn as_array(&self) -> Option<&Vec<Value>> {
-        match *self {
-            Value::Array(ref array) => Some(&*array),
-            _ => None,
+    /// Inserts the result of `default` if the key is absent, then returns the
+    /// key's value.
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut Value
+        where F: FnOnce() -> Value,
+    {
+        match self {
+            Entry::Occupied(value) => value,
+            Entry::Vacant(entry) => entry.insert(default()),
         }
     }
 
-    /// If the `Value` is an Array, returns the associated mutable vector.
-    /// Returns None otherwise.
-    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Value>> {
-        match *self {
-            Value::Array(ref mut list) => Some(list),
-            _ => None,
+    /// Calls `f` on the key's value if the key is present.
+    pub fn and_modify<F>(self, f: F) -> Self
+        where F: FnOnce(&mut Value),
+    {
+        match self {
+            Entry::Occupied(value) => {
+                f(value);
+                Entry::Occupied(value)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
         }
     }
+}
 
-    /// Returns true if the `Value` is a String. Returns false otherwise.
-    pub fn is_string(&self) -> bool {
-        self.as_str().is_some()
+/// An absent key of a JSON object.
+#[cfg(not(feature = "preserve_order"))]
+pub struct VacantEntry<'a> {
+    entry: btree_map::VacantEntry<'a, String, Value>,
+}
+
+/// An absent key of a JSON object.
+#[cfg(feature = "preserve_order")]
+pub struct VacantEntry<'a> {
+    map: &'a mut Map<String, Value>,
+    key: String,
+}
+
+impl<'a> VacantEntry<'a> {
+    /// Returns the key that would be inserted.
+    #[cfg(not(feature = "preserve_order"))]
+    pub fn key(&self) -> &str {
+        self.entry.key()
     }
 
-    /// If the `Value` is a String, returns the associated str.
-    /// Returns None otherwise.
-    pub fn as_str(&self) -> Option<&str> {
-        match *self {
-            Value::String(ref s) => Some(s),
-            _ => None,
-        }
+    /// Returns the key that would be inserted.
+    #[cfg(feature = "preserve_order")]
+    pub fn key(&self) -> &str {
+        &self.key
     }
 
-    /// Returns true if the `Value` is a Number. Returns false otherwise.
-    pub fn is_number(&self) -> bool {
-        match *self {
-            Value::I64(_) | Value::U64(_) | Value::F64(_) => true,
-            _ => false,
-        }
+    /// Inserts `value` under the key and returns a reference to it.
+    #[cfg(not(feature = "preserve_order"))]
+    pub fn insert(self, value: Value) -> &'a mut Value {
+        self.entry.insert(value)
     }
 
-    /// Returns true if the `Value` is a i64. Returns false otherwise.
-    pub fn is_i64(&self) -> bool {
-        match *self {
-            Value::I64(_) => true,
-            _ => false,
-        }
+    /// Inserts `value` under the key and returns a reference to it.
+    #[cfg(feature = "preserve_order")]
+    pub fn insert(self, value: Value) -> &'a mut Value {
+        self.map.insert(self.key.clone(), value);
+        self.map.get_mut(&self.key).unwrap()
     }
+}
 
-    /// Returns true if the `Value` is a u64. Returns false otherwise.
-    pub fn is_u64(&self) -> bool {
-        match *self {
-            Value::U64(_) => true,
-            _ => false,
+#[cfg(not(feature = "preserve_order"))]
+fn map_entry(map: &mut Map<String, Value>, key: String) -> Entry {
+    match map.entry(key) {
+        btree_map::Entry::Occupied(entry) => Entry::Occupied(entry.into_mut()),
+        btree_map::Entry::Vacant(entry) => {
+            Entry::Vacant(VacantEntry { entry: entry })
         }
     }
+}
 
-    /// Returns true if the `Value` is a f64. Returns false otherwise.
-    pub fn is_f64(&self) -> bool {
-        match *self {
-            Value::F64(_) => true,
-            _ => false,
+// LinkedHashMap 0.3 has no entry API of its own.
+#[cfg(feature = "preserve_order")]
+fn map_entry(map: &mut Map<String, Value>, key: String) -> Entry {
+    if map.contains_key(&key) {
+        Entry::Occupied(map.get_mut(&key).unwrap())
+    } else {
+        Entry::Vacant(VacantEntry {
+            map: map,
+            key: key,
+        })
+    }
+}
+
+/// A type that can index into a `Value`: `usize` for arrays and `str` or
+/// `String` for objects.
+pub trait Index {
+    /// Return the value at this index in `v`, if there is one.
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value>;
+
+    /// Return the mutable value at this index in `v`, if there is one.
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value>;
+}
+
+impl Index for usize {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        match *v {
+            Value::Array(ref vec) => vec.get(*self),
+            _ => None,
         }
     }
 
-    /// If the `Value` is a number, return or cast it to a i64.
-    /// Returns None otherwise.
-    pub fn as_i64(&self) -> Option<i64> {
-        match *self {
-            Value::I64(n) => Some(n),
-            Value::U64(n) => NumCast::from(n),
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        match *v {
+            Value::Array(ref mut vec) => vec.get_mut(*self),
             _ => None,
         }
     }
+}
 
-    /// If the `Value` is a number, return or cast it to a u64.
-    /// Returns None otherwise.
-    pub fn as_u64(&self) -> Option<u64> {
-        match *self {
-            Value::I64(n) => NumCast::from(n),
-            Value::U64(n) => Some(n),
+impl Index for str {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        match *v {
+            Value::Object(ref map) => map.get(self),
             _ => None,
         }
     }
 
-    /// If the `Value` is a number, return or cast it to a f64.
-    /// Returns None otherwise.
-    pub fn as_f64(&self) -> Option<f64> {
-        match *self {
-            Value::I64(n) => NumCast::from(n),
-            Value::U64(n) => NumCast::from(n),
-            Value::F64(n) => Some(n),
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        match *v {
+            Value::Object(ref mut map) => map.get_mut(self),
             _ => None,
         }
     }
+}
+
+impl Index for String {
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        self[..].index_into(v)
+    }
 
-    /// Returns true if the `Value` is a Boolean. Returns false otherwise.
-    pub fn is_boolean(&self) -> bool {
-        self.as_bool().is_some()
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        self[..].index_into_mut(v)
     }
+}
 
-    /// If the `Value` is a Boolean, returns the associated bool.
-    /// Returns None otherwise.
-    pub fn as_bool(&self) -> Option<bool> {
-        match *self {
-            Value::Bool(b) => Some(b),
-            _ => None,
+impl<'a, T: ?Sized> Index for &'a T
+    where T: Index,
+{
+    fn index_into<'v>(&self, v: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(v)
+    }
+
+    fn index_into_mut<'v>(&self, v: &'v mut Value) -> Option<&'v mut Value> {
+        (**self).index_into_mut(v)
+    }
+}
+
+/// Callbacks for `Value::walk`, each given the JSON Pointer of the value it
+/// is called for. Every method does nothing by default, so an implementation
+/// only needs to provide the ones it uses.
+pub trait Visitor {
+    /// Called on reaching an array, before any of its elements.
+    fn enter_array(&mut self, _path: &str, _array: &[Value]) {}
+
+    /// Called after all of an array's elements have been visited.
+    fn leave_array(&mut self, _path: &str, _array: &[Value]) {}
+
+    /// Called on reaching an object, before any of its values.
+    fn enter_object(&mut self, _path: &str, _object: &Map<String, Value>) {}
+
+    /// Called after all of an object's values have been visited.
+    fn leave_object(&mut self, _path: &str, _object: &Map<String, Value>) {}
+
+    /// Called for every value that is not an array or object.
+    fn scalar(&mut self, _path: &str, _value: &Value) {}
+}
+
+/// The error returned when a `Value::try_into_*` conversion fails. It holds
+/// the value that could not be converted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeError {
+    expected: &'static str,
+    value: Value,
+}
+
+impl TypeError {
+    fn new(expected: &'static str, value: Value) -> Self {
+        TypeError {
+            expected: expected,
+            value: value,
         }
     }
 
-    /// Returns true if the `Value` is a Null. Returns false otherwise.
-    pub fn is_null(&self) -> bool {
-        self.as_null().is_some()
+    /// The type the value was being converted to, such as `"u64"`.
+    pub fn expected(&self) -> &'static str {
+        self.expected
     }
 
-    /// If the `Value` is a Null, returns ().
-    /// Returns None otherwise.
-    pub fn as_null(&self) -> Option<()> {
-        match *self {
-            Value::Null => Some(()),
-            _ => None,
+    /// The kind of value that was found instead, such as `"string"`.
+    pub fn found(&self) -> &'static str {
+        self.value.kind_name()
+    }
+
+    /// Returns the value that could not be converted.
+    pub fn into_value(self) -> Value {
+        self.value
+    }
+}
+
+impl error::Error for TypeError {
+    fn description(&self) -> &str {
+        "invalid type"
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "invalid type: expected {}, found {}",
+               self.expected,
+               self.found())
+    }
+}
+
+/// Values are equal if they are the same kind and have equal contents. An
+/// `I64` and a `U64` holding the same number are equal, but an `F64` is never
+/// equal to an integer.
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (&Value::Null, &Value::Null) => true,
+            (&Value::Bool(a), &Value::Bool(b)) => a == b,
+            (&Value::I64(a), &Value::I64(b)) => a == b,
+            (&Value::U64(a), &Value::U64(b)) => a == b,
+            (&Value::I64(a), &Value::U64(b)) |
+            (&Value::U64(b), &Value::I64(a)) => a >= 0 && a as u64 == b,
+            (&Value::F64(a), &Value::F64(b)) => a == b,
+            #[cfg(feature = "arbitrary_precision")]
+            (&Value::BigNumber(ref a), &Value::BigNumber(ref b)) => a == b,
+            (&Value::String(ref a), &Value::String(ref b)) => a == b,
+            (&Value::Array(ref a), &Value::Array(ref b)) => a == b,
+            (&Value::Object(ref a), &Value::Object(ref b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// See `Value::total_cmp` for the order. Comparisons involving `NaN` return
+/// None.
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (&Value::Array(ref a), &Value::Array(ref b)) => a.partial_cmp(b),
+            (&Value::Object(ref a), &Value::Object(ref b)) => {
+                a.iter().partial_cmp(b.iter())
+            }
+            _ => self.partial_cmp_scalar(other),
+        }
+    }
+}
+
+fn kind_rank(value: &Value) -> u8 {
+    match *value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+        _ => 2,
+    }
+}
+
+fn number_rank(value: &Value) -> u8 {
+    match *value {
+        Value::I64(_) | Value::U64(_) => 0,
+        Value::F64(_) => 1,
+        _ => 2,
+    }
+}
+
+fn is_nan(value: &Value) -> bool {
+    match *value {
+        Value::F64(f) => f.is_nan(),
+        _ => false,
+    }
+}
+
+// Compares two numbers exactly, falling back to the variant when they are
+// equal in value so that the result agrees with `PartialEq`.
+fn compare_numbers(a: &Value, b: &Value) -> Option<Ordering> {
+    let ord = match (a.as_i128(), b.as_i128()) {
+        (Some(x), Some(y)) => Some(x.cmp(&y)),
+        (Some(x), None) => b.as_f64().and_then(|y| compare_int_float(x, y)),
+        (None, Some(y)) => {
+            a.as_f64()
+                .and_then(|x| compare_int_float(y, x))
+                .map(Ordering::reverse)
         }
+        (None, None) => {
+            match (a.as_f64(), b.as_f64()) {
+                (Some(x), Some(y)) => x.partial_cmp(&y),
+                _ => None,
+            }
+        }
+    };
+    match ord {
+        Some(Ordering::Equal) => Some(number_rank(a).cmp(&number_rank(b))),
+        ord => ord,
+    }
+}
+
+// Compares an integer to a float without losing precision on large values.
+fn compare_int_float(i: i128, f: f64) -> Option<Ordering> {
+    match (i as f64).partial_cmp(&f) {
+        Some(Ordering::Equal) => Some(i.cmp(&(f as i128))),
+        ord => ord,
     }
 }
 
@@ -378,6 +2341,10 @@ impl ser::Serialize for Value {
             Value::I64(v) => serializer.serialize_i64(v),
             Value::U64(v) => serializer.serialize_u64(v),
             Value::F64(v) => serializer.serialize_f64(v),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigNumber(ref v) => {
+                serializer.serialize_newtype_struct(::NUMBER_TOKEN, v)
+            }
             Value::String(ref v) => serializer.serialize_str(v),
             Value::Array(ref v) => v.serialize(serializer),
             Value::Object(ref v) => v.serialize(serializer),
@@ -460,6 +2427,7 @@ impl de::Deserialize for Value {
                 Ok(Value::Array(values))
             }
 
+            #[cfg(not(feature = "arbitrary_precision"))]
             #[inline]
             fn visit_map<V>(&mut self, visitor: V) -> Result<Value, V::Error>
                 where V: de::MapVisitor,
@@ -467,6 +2435,33 @@ impl de::Deserialize for Value {
                 let values = try!(MapVisitor::new().visit_map(visitor));
                 Ok(Value::Object(values))
             }
+
+            #[cfg(feature = "arbitrary_precision")]
+            fn visit_map<V>(&mut self, mut visitor: V) -> Result<Value, V::Error>
+                where V: de::MapVisitor,
+            {
+                let mut values = Map::new();
+                while let Some(key) = try!(visitor.visit_key()) {
+                    match key {
+                        MapKey::Key(key) => {
+                            let value = try!(visitor.visit_value());
+                            values.insert(key, value);
+                        }
+                        // A big number arrives as a map with a single entry
+                        // under a private key.
+                        MapKey::Number => {
+                            let number: String = try!(visitor.visit_value());
+                            try!(visitor.end());
+                            if !number::is_valid(&number) {
+                                return Err(de::Error::invalid_value(&number));
+                            }
+                            return Ok(Value::BigNumber(number));
+                        }
+                    }
+                }
+                try!(visitor.end());
+                Ok(Value::Object(values))
+            }
         }
 
         deserializer.deserialize(ValueVisitor)
@@ -497,20 +2492,22 @@ impl<'a, 'b> io::Write for WriterFormatter<'a, 'b> {
 impl fmt::Debug for Value {
     /// Serializes a json value into a string
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut wr = WriterFormatter {
-            inner: f,
-        };
-        super::ser::to_writer(&mut wr, self).map_err(|_| fmt::Error)
+        fmt::Display::fmt(self, f)
     }
 }
 
 impl fmt::Display for Value {
-    /// Serializes a json value into a string
+    /// Serializes a json value into a string. Non-finite floats, which
+    /// `to_string` rejects, are written as `NaN`, `Infinity` and `-Infinity`.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut wr = WriterFormatter {
+        let wr = WriterFormatter {
             inner: f,
         };
-        super::ser::to_writer(&mut wr, self).map_err(|_| fmt::Error)
+        let options = SerializeOptions::new().emit_non_finite(true);
+        let mut ser = super::ser::Serializer::with_options(wr,
+                                                          CompactFormatter,
+                                                          options);
+        ser::Serialize::serialize(self, &mut ser).map_err(|_| fmt::Error)
     }
 }
 
@@ -643,11 +2640,7 @@ impl ser::Serializer for Serializer {
 
     #[inline]
     fn serialize_f64(&mut self, value: f64) -> Result<(), Error> {
-        self.value = if value.is_finite() {
-            Value::F64(value)
-        } else {
-            Value::Null
-        };
+        self.value = Value::F64(value);
         Ok(())
     }
 
@@ -698,11 +2691,30 @@ impl ser::Serializer for Serializer {
     #[inline]
     fn serialize_newtype_struct<T>(
         &mut self,
-        _name: &'static str,
+        name: &'static str,
         value: T
     ) -> Result<(), Error>
         where T: ser::Serialize,
     {
+        if name == ::RAW_TOKEN {
+            if let Value::String(raw) = to_value(&value) {
+                self.value = try!(::de::from_str(&raw));
+                return Ok(());
+            }
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        {
+            if name == ::NUMBER_TOKEN {
+                if let Value::String(number) = to_value(&value) {
+                    if !number::is_valid(&number) {
+                        return Err(Error::Syntax(ErrorCode::InvalidNumber, 0,
+                                                 0));
+                    }
+                    self.value = Value::BigNumber(number);
+                    return Ok(());
+                }
+            }
+        }
         value.serialize(self)
     }
 
@@ -977,6 +2989,22 @@ impl de::Deserializer for Deserializer {
             Value::I64(v) => visitor.visit_i64(v),
             Value::U64(v) => visitor.visit_u64(v),
             Value::F64(v) => visitor.visit_f64(v),
+            #[cfg(feature = "arbitrary_precision")]
+            Value::BigNumber(v) => {
+                match number::visit(&mut visitor, &v) {
+                    Ok(value) => Ok(value),
+                    // Only `Value` understands the private map, so give
+                    // everything else the nearest `f64`.
+                    Err(_) => {
+                        let f = v.parse().unwrap_or(f64::INFINITY);
+                        if f.is_infinite() {
+                            Err(Error::Syntax(ErrorCode::NumberOutOfRange, 0, 0))
+                        } else {
+                            visitor.visit_f64(f)
+                        }
+                    }
+                }
+            }
             Value::String(v) => visitor.visit_string(v),
             Value::Array(v) => {
                 let len = v.len();
@@ -1337,3 +3365,51 @@ impl<T: ?Sized> ToJson for T
         to_value(&self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::unescape_token;
+
+    #[test]
+    fn test_unescape_token_borrows_without_escapes() {
+        match unescape_token("abc") {
+            Cow::Borrowed(token) => assert_eq!(token, "abc"),
+            Cow::Owned(_) => panic!("allocated for a token without escapes"),
+        }
+        match unescape_token("a~1b") {
+            Cow::Owned(token) => assert_eq!(token, "a/b"),
+            Cow::Borrowed(_) => panic!("borrowed an escaped token"),
+        }
+        assert_eq!(unescape_token("~01"), "~1");
+    }
+
+    // Every pointer lookup unescapes its tokens this way, so none of them
+    // copies a token that needs no unescaping.
+    #[test]
+    fn test_pointer_tokens_borrow_the_pointer() {
+        let pointer = "/a/B~01/c~1d/0";
+        let tokens: Vec<_> = pointer.split('/')
+            .skip(1)
+            .map(unescape_token)
+            .collect();
+        assert_eq!(tokens, vec!["a", "B~1", "c/d", "0"]);
+        for &i in &[0, 3] {
+            match tokens[i] {
+                Cow::Borrowed(token) => {
+                    let start = pointer.as_ptr() as usize;
+                    let at = token.as_ptr() as usize;
+                    assert!(start < at && at < start + pointer.len());
+                }
+                Cow::Owned(_) => panic!("copied {:?}", tokens[i]),
+            }
+        }
+        for &i in &[1, 2] {
+            match tokens[i] {
+                Cow::Owned(_) => {}
+                Cow::Borrowed(_) => panic!("borrowed {:?}", tokens[i]),
+            }
+        }
+    }
+}
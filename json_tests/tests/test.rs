@@ -9,6 +9,7 @@
 extern crate serde_derive;
 
 extern crate serde;
+#[macro_use]
 extern crate serde_json;
 extern crate skeptic;
 
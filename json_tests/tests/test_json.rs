@@ -4,9 +4,10 @@ use std::i64;
 use std::iter;
 use std::marker::PhantomData;
 use std::u64;
+use std::usize;
 
 use serde::de;
-use serde::ser;
+use serde::ser::{self, Serialize};
 use serde::bytes::{ByteBuf, Bytes};
 
 use serde_json::{
@@ -21,7 +22,11 @@ use serde_json::{
     to_value,
 };
 
+use serde_json::de::{ParseOptions, from_reader_with_options,
+                     from_slice_with_options, from_str_with_options};
 use serde_json::error::{Error, ErrorCode};
+use serde_json::ser::{ArraySerializer, CompactFormatter, FloatFormat,
+                      SerializeOptions};
 
 macro_rules! treemap {
     () => {
@@ -134,18 +139,180 @@ fn test_write_f64() {
 }
 
 #[test]
-fn test_encode_nonfinite_float_yields_null() {
+fn test_encode_nonfinite_float_errors() {
     let v = to_value(::std::f64::NAN);
-    assert!(v.is_null());
+    assert!(v.as_f64().unwrap().is_nan());
 
     let v = to_value(::std::f64::INFINITY);
-    assert!(v.is_null());
+    assert_eq!(v, Value::F64(::std::f64::INFINITY));
 
     let v = to_value(::std::f32::NAN);
-    assert!(v.is_null());
+    assert!(v.as_f64().unwrap().is_nan());
 
     let v = to_value(::std::f32::INFINITY);
-    assert!(v.is_null());
+    assert_eq!(v, Value::F64(::std::f64::INFINITY));
+
+    let err = Error::Syntax(ErrorCode::NonFiniteFloat, 0, 0);
+    assert_eq!(serde_json::to_string(&::std::f64::NAN).unwrap_err(), err);
+    assert_eq!(serde_json::to_string(&::std::f32::NEG_INFINITY).unwrap_err(), err);
+    assert_eq!(serde_json::to_string(&Value::F64(::std::f64::INFINITY)).unwrap_err(),
+               err);
+
+    // Display writes the non-standard tokens rather than failing.
+    assert_eq!(Value::F64(::std::f64::NAN).to_string(), "NaN");
+}
+
+#[test]
+fn test_encode_nonfinite_float_with_options() {
+    let value = vec![f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1.5];
+
+    let mut writer = Vec::new();
+    {
+        let options = SerializeOptions::new().emit_non_finite(true);
+        let mut ser = serde_json::Serializer::with_options(&mut writer,
+                                                           CompactFormatter,
+                                                           options);
+        value.serialize(&mut ser).unwrap();
+    }
+    assert_eq!(writer, b"[NaN,Infinity,-Infinity,1.5]");
+
+    // With the default options they are an error.
+    let mut writer = Vec::new();
+    let mut ser = serde_json::Serializer::with_options(&mut writer,
+                                                       CompactFormatter,
+                                                       SerializeOptions::new());
+    assert_eq!(value.serialize(&mut ser).unwrap_err(),
+               Error::Syntax(ErrorCode::NonFiniteFloat, 0, 0));
+}
+
+#[test]
+fn test_encode_float_format() {
+    fn to_string_with<T: Serialize>(value: &T, format: FloatFormat) -> String {
+        let mut writer = Vec::new();
+        {
+            let options = SerializeOptions::new().float_format(format);
+            let mut ser = serde_json::Serializer::with_options(&mut writer,
+                                                               CompactFormatter,
+                                                               options);
+            value.serialize(&mut ser).unwrap();
+        }
+        String::from_utf8(writer).unwrap()
+    }
+
+    // The default is the shortest form that still reads back as a float.
+    assert_eq!(serde_json::to_string(&Value::F64(1.0)).unwrap(), "1.0");
+    let json = serde_json::to_string(&Value::F64(1.0)).unwrap();
+    assert_eq!(from_str::<Value>(&json).unwrap(), Value::F64(1.0));
+
+    let value = vec![1.0, 0.25, -3.0, 1e300];
+    assert_eq!(to_string_with(&value, FloatFormat::Shortest),
+               serde_json::to_string(&value).unwrap());
+    assert_eq!(to_string_with(&value, FloatFormat::PreserveInteger),
+               "[1,0.25,-3,1e300]");
+    assert_eq!(to_string_with(&&value[..3], FloatFormat::FixedPrecision(2)),
+               "[1.00,0.25,-3.00]");
+    assert_eq!(to_string_with(&vec![0.125f64], FloatFormat::FixedPrecision(0)),
+               "[0]");
+    assert_eq!(to_string_with(&vec![2.0f32, 0.1f32], FloatFormat::PreserveInteger),
+               "[2,0.1]");
+}
+
+#[test]
+fn test_escape_non_ascii() {
+    fn to_ascii_string<T: Serialize>(value: &T) -> String {
+        let mut writer = Vec::new();
+        {
+            let options = SerializeOptions::new().escape_non_ascii(true);
+            let mut ser = serde_json::Serializer::with_options(&mut writer,
+                                                               CompactFormatter,
+                                                               options);
+            value.serialize(&mut ser).unwrap();
+        }
+        String::from_utf8(writer).unwrap()
+    }
+
+    // Control characters are always escaped.
+    assert_eq!(serde_json::to_string(&"\n\t\u{0}\u{1f}").unwrap(),
+               r#""\n\t\u0000\u001f""#);
+
+    assert_eq!(to_ascii_string(&"\u{1F600}"), r#""\ud83d\ude00""#);
+    assert_eq!(to_ascii_string(&"a\u{e9}\n\u{4e2d}b"), r#""a\u00e9\n\u4e2db""#);
+    assert_eq!(to_ascii_string(&'\u{e9}'), r#""\u00e9""#);
+    assert_eq!(to_ascii_string(&treemap!("\u{e9}" => 1)), r#"{"\u00e9":1}"#);
+
+    let s = "x\u{1F600}\u{7f}\u{80}\u{ffff}";
+    assert_eq!(from_str::<String>(&to_ascii_string(&s)).unwrap(), s);
+}
+
+#[test]
+fn test_serialize_options_reuse() {
+    let options = SerializeOptions::new()
+        .pretty(true)
+        .indent(4)
+        .float_format(FloatFormat::FixedPrecision(2))
+        .escape_non_ascii(true)
+        .emit_non_finite(true);
+
+    let value: Value = from_str(r#"{"name": "café", "values": [1.5, 2]}"#)
+        .unwrap();
+    let expected = indoc!("
+        {
+            \"name\": \"caf\\u00e9\",
+            \"values\": [
+                1.50,
+                2
+            ]
+        }");
+    assert_eq!(options.to_string(&value).unwrap(), expected);
+    assert_eq!(options.to_vec(&value).unwrap(), expected.as_bytes());
+    let mut writer = Vec::new();
+    options.to_writer(&mut writer, &value).unwrap();
+    assert_eq!(writer, expected.as_bytes());
+
+    assert_eq!(options.to_string(&f64::NAN).unwrap(), "NaN");
+
+    let compact = options.clone().pretty(false);
+    assert_eq!(compact.to_string(&value).unwrap(),
+               r#"{"name":"caf\u00e9","values":[1.50,2]}"#);
+    let two_spaces = SerializeOptions::new().pretty(true);
+    assert_eq!(two_spaces.to_string(&value).unwrap(),
+               serde_json::to_string_pretty(&value).unwrap());
+}
+
+#[test]
+fn test_array_serializer() {
+    let values = vec![
+        Value::U64(1),
+        from_str(r#"{"a": [true, null]}"#).unwrap(),
+        Value::String("x".to_owned()),
+    ];
+
+    let mut array = ArraySerializer::new(Vec::new());
+    for value in &values {
+        array.push(value).unwrap();
+    }
+    let json = String::from_utf8(array.end().unwrap()).unwrap();
+    assert_eq!(json, serde_json::to_string(&Value::Array(values.clone())).unwrap());
+
+    let mut array = ArraySerializer::with_serializer(serde_json::Serializer::pretty(Vec::new()));
+    for value in &values {
+        array.push(value).unwrap();
+    }
+    let json = String::from_utf8(array.end().unwrap()).unwrap();
+    assert_eq!(json, serde_json::to_string_pretty(&Value::Array(values)).unwrap());
+
+    let array = ArraySerializer::with_serializer(serde_json::Serializer::pretty(Vec::new()));
+    assert_eq!(array.end().unwrap(), b"[]");
+
+    // A borrowed writer can be used and inspected afterwards.
+    let mut writer = Vec::new();
+    {
+        let mut array = ArraySerializer::new(&mut writer);
+        array.push("a").unwrap();
+        array.push(&2).unwrap();
+        array.end().unwrap();
+    }
+    assert_eq!(writer, br#"["a",2]"#);
 }
 
 #[test]
@@ -158,6 +325,16 @@ fn test_write_str() {
     test_pretty_encode_ok(tests);
 }
 
+#[test]
+fn test_value_display() {
+    let value: Value = serde_json::from_str(
+        r#"{"a\"b": ["say \"hi\"\n", 0.1, 1e300, -3, null]}"#).unwrap();
+    let s = value.to_string();
+    assert_eq!(s, r#"{"a\"b":["say \"hi\"\n",0.1,1e300,-3,null]}"#);
+    assert_eq!(serde_json::from_str::<Value>(&s).unwrap(), value);
+    assert_eq!(format!("{}", Value::String("\"".to_owned())), r#""\"""#);
+}
+
 #[test]
 fn test_write_bool() {
     let tests = &[
@@ -677,6 +854,66 @@ fn test_write_newtype_struct() {
     ]);
 }
 
+#[test]
+fn test_to_value_hand_written_serialize() {
+    struct Point {
+        x: u64,
+        label: Option<String>,
+    }
+
+    impl ser::Serialize for Point {
+        fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+            where S: ser::Serializer,
+        {
+            let mut state = try!(serializer.serialize_map(Some(2)));
+            try!(serializer.serialize_map_key(&mut state, "x"));
+            try!(serializer.serialize_map_value(&mut state, self.x));
+            try!(serializer.serialize_map_key(&mut state, "label"));
+            try!(serializer.serialize_map_value(&mut state, &self.label));
+            serializer.serialize_map_end(state)
+        }
+    }
+
+    assert_eq!(to_value(&vec![1u64, 2]),
+               Value::Array(vec![Value::U64(1), Value::U64(2)]));
+    assert_eq!(to_value(&Some("a")), Value::String("a".to_owned()));
+    assert_eq!(to_value(&treemap!("k" => -1i32)),
+               Value::Object(treemap!("k".to_owned() => Value::I64(-1))));
+
+    let point = Point { x: 1, label: None };
+    assert_eq!(to_value(&point),
+               Value::Object(treemap!(
+                   "label".to_owned() => Value::Null,
+                   "x".to_owned() => Value::U64(1)
+               )));
+    assert_eq!(serde_json::to_string(&point).unwrap(), r#"{"x":1,"label":null}"#);
+}
+
+#[test]
+fn test_write_to_writer() {
+    let value: Value = from_str(r#"{"a":[1,-2,3.5,null],"b":{"c":"d\n"},"e":true}"#).unwrap();
+
+    let mut writer = Vec::new();
+    serde_json::to_writer(&mut writer, &value).unwrap();
+    assert_eq!(writer, serde_json::to_string(&value).unwrap().into_bytes());
+
+    let mut writer = Vec::new();
+    serde_json::to_writer_pretty(&mut writer, &value).unwrap();
+    assert_eq!(writer, serde_json::to_string_pretty(&value).unwrap().into_bytes());
+}
+
+#[test]
+fn test_write_to_vec() {
+    let value: Value = from_str(r#"{"a":[1,-2,3.5,null],"b":"\u00e9\ud83d\ude00"}"#).unwrap();
+
+    let bytes = serde_json::to_vec(&value).unwrap();
+    assert_eq!(bytes, serde_json::to_string(&value).unwrap().as_bytes());
+    assert!(bytes.ends_with("\"b\":\"\u{e9}\u{1f600}\"}".as_bytes()));
+
+    let bytes = serde_json::to_vec_pretty(&value).unwrap();
+    assert_eq!(bytes, serde_json::to_string_pretty(&value).unwrap().as_bytes());
+}
+
 fn test_parse_ok<T>(tests: Vec<(&str, T)>)
     where T: Clone + Debug + PartialEq + ser::Serialize + de::Deserialize,
 {
@@ -723,16 +960,9 @@ fn test_parse_unusual_ok<T>(tests: Vec<(&str, T)>)
 
 macro_rules! test_parse_err {
     ($name:ident::<$($ty:ty),*>($arg:expr) => $err:expr) => {
-        match ($err, &$name::<$($ty),*>($arg).unwrap_err()) {
-            (
-                &Error::Syntax(ref expected_code, expected_line, expected_col),
-                &Error::Syntax(ref actual_code, actual_line, actual_col),
-            ) if expected_code == actual_code
-                && expected_line == actual_line
-                && expected_col == actual_col => { /* pass */ }
-            (expected_err, actual_err) => {
-                panic!("unexpected {} error: {}, expected: {}", stringify!($name), actual_err, expected_err)
-            }
+        let actual_err = $name::<$($ty),*>($arg).unwrap_err();
+        if actual_err != *$err {
+            panic!("unexpected {} error: {}, expected: {}", stringify!($name), actual_err, $err)
         }
     };
 }
@@ -830,6 +1060,54 @@ fn test_parse_number_errors() {
     ]);
 }
 
+#[test]
+fn test_parse_non_finite() {
+    let options = ParseOptions::new().allow_non_finite(true);
+
+    let v: Value = from_str_with_options("NaN", &options).unwrap();
+    assert!(v.as_f64().unwrap().is_nan());
+    let v: Value = from_str_with_options(" Infinity ", &options).unwrap();
+    assert_eq!(v, Value::F64(f64::INFINITY));
+    let v: Value = from_str_with_options("-Infinity", &options).unwrap();
+    assert_eq!(v, Value::F64(f64::NEG_INFINITY));
+    let v: Vec<f64> = from_str_with_options("[-Infinity,1]", &options).unwrap();
+    assert_eq!(v, vec![f64::NEG_INFINITY, 1.0]);
+
+    assert_eq!(from_str_with_options::<Value>("Infinit", &options),
+               Err(Error::Syntax(ErrorCode::ExpectedSomeIdent, 1, 7)));
+
+    // Strict by default.
+    test_parse_err::<Value>(vec![
+        ("NaN", Error::Syntax(ErrorCode::ExpectedSomeValue, 1, 1)),
+        ("Infinity", Error::Syntax(ErrorCode::ExpectedSomeValue, 1, 1)),
+        ("-Infinity", Error::Syntax(ErrorCode::InvalidNumber, 1, 2)),
+    ]);
+}
+
+#[test]
+fn test_parse_loose_numbers() {
+    let options = ParseOptions::new().allow_loose_numbers(true);
+
+    let tests = vec![
+        ("+5", Value::U64(5)),
+        (".5", Value::F64(0.5)),
+        ("5.", Value::F64(5.0)),
+        ("-.25", Value::F64(-0.25)),
+        ("+.5e1", Value::F64(5.0)),
+        ("5.e2", Value::F64(500.0)),
+        ("[+1, 2.]", Value::Array(vec![Value::U64(1), Value::F64(2.0)])),
+    ];
+    for (s, expected) in tests {
+        assert_eq!(from_str_with_options::<Value>(s, &options).unwrap(),
+                   expected);
+        assert!(from_str::<Value>(s).is_err());
+    }
+
+    for s in &[".", "+", "-.", "+-5", "++5", "+.", "+05"] {
+        assert!(from_str_with_options::<Value>(s, &options).is_err());
+    }
+}
+
 #[test]
 fn test_parse_i64() {
     test_parse_ok(vec![
@@ -890,34 +1168,91 @@ fn test_parse_f64() {
         (&format!("{:?}", (u64::MAX as f64) + 1.0), (u64::MAX as f64) + 1.0),
         (&format!("{:?}", f64::EPSILON), f64::EPSILON),
         ("0.0000000000000000000000000000000000000000000000000123e50", 1.23),
-        ("100e-777777777777777777777777777", 0.0),
-        ("1010101010101010101010101010101010101010", 10101010101010101010e20),
-        ("0.1010101010101010101010101010101010101010", 0.1010101010101010101),
         ("0e1000000000000000000000000000000000000000000000", 0.0),
         ("1000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
-           00000000", 1e308),
+           .0e8", 1e308),
         ("1000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
-           .0e8", 1e308),
+           e8", 1e308),
         ("1000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
-           e8", 1e308),
+           000000000000000000e-10", 1e308),
+    ]);
+
+    // These have more precision than an `f64` holds, so with
+    // `arbitrary_precision` a `Value` keeps their text instead.
+    let lossy = vec![
+        ("100e-777777777777777777777777777", 0.0),
+        ("0.1010101010101010101010101010101010101010", 0.1010101010101010101),
+    ];
+    #[cfg(not(feature = "arbitrary_precision"))]
+    {
+        test_parse_ok(lossy);
+    }
+    #[cfg(feature = "arbitrary_precision")]
+    {
+        for (s, f) in lossy {
+            assert_eq!(from_str::<f64>(s).unwrap(), f);
+            assert_eq!(from_str::<Value>(s).unwrap(), Value::BigNumber(s.to_owned()));
+        }
+    }
+}
+
+#[test]
+fn test_parse_integer_boundaries() {
+    assert_eq!(from_str::<Value>("18446744073709551615").unwrap(),
+               Value::U64(u64::MAX));
+    assert_eq!(from_str::<Value>("9223372036854775808").unwrap(),
+               Value::U64(9223372036854775808));
+    assert_eq!(from_str::<Value>("-9223372036854775808").unwrap(),
+               Value::I64(i64::MIN));
+    assert_eq!(from_str::<Value>("-0").unwrap(), Value::I64(0));
+
+    // Integers beyond both ranges fall back to the nearest `f64`.
+    #[cfg(not(feature = "arbitrary_precision"))]
+    {
+        assert_eq!(from_str::<Value>("18446744073709551616").unwrap(),
+                   Value::F64(18446744073709551616.0));
+        assert_eq!(from_str::<Value>("-9223372036854775809").unwrap(),
+                   Value::F64(-9223372036854775808.0));
+        assert_eq!(from_str::<Value>("-18446744073709551615").unwrap(),
+                   Value::F64(-18446744073709551615.0));
+    }
+    #[cfg(feature = "arbitrary_precision")]
+    {
+        assert_eq!(from_str::<Value>("18446744073709551616").unwrap(),
+                   Value::BigNumber("18446744073709551616".to_owned()));
+        assert_eq!(from_str::<Value>("-9223372036854775809").unwrap(),
+                   Value::BigNumber("-9223372036854775809".to_owned()));
+    }
+
+    assert_eq!(from_str::<f64>("18446744073709551616").unwrap(),
+               18446744073709551616.0);
+}
+
+// With `arbitrary_precision` these integers parse into `Value::BigNumber`
+// rather than `Value::F64`.
+#[cfg(not(feature = "arbitrary_precision"))]
+#[test]
+fn test_parse_long_integer_as_f64() {
+    test_parse_ok(vec![
+        ("1010101010101010101010101010101010101010", 10101010101010101010e20),
         ("1000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
            000000000000000000000000000000000000000000000000000000000000\
-           000000000000000000e-10", 1e308),
+           00000000", 1e308),
     ]);
 }
 
@@ -1105,6 +1440,60 @@ fn test_parse_struct() {
     );
 }
 
+#[test]
+fn test_parse_hand_written_deserialize() {
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: u64,
+    }
+
+    struct PointVisitor;
+
+    impl de::Visitor for PointVisitor {
+        type Value = Point;
+
+        fn visit_map<V>(&mut self, mut visitor: V) -> Result<Point, V::Error>
+            where V: de::MapVisitor,
+        {
+            let mut x = None;
+            while let Some(key) = try!(visitor.visit_key::<String>()) {
+                if key == "x" {
+                    x = Some(try!(visitor.visit_value()));
+                } else {
+                    try!(visitor.visit_value::<de::impls::IgnoredAny>());
+                }
+            }
+            try!(visitor.end());
+            match x {
+                Some(x) => Ok(Point { x: x }),
+                None => visitor.missing_field("x"),
+            }
+        }
+    }
+
+    impl de::Deserialize for Point {
+        fn deserialize<D>(deserializer: &mut D) -> Result<Point, D::Error>
+            where D: de::Deserializer,
+        {
+            deserializer.deserialize_map(PointVisitor)
+        }
+    }
+
+    let v: Vec<u64> = from_str("[1,2,3]").unwrap();
+    assert_eq!(v, vec![1, 2, 3]);
+    let v: Option<String> = from_str(r#""a""#).unwrap();
+    assert_eq!(v, Some("a".to_owned()));
+
+    assert_eq!(from_str::<Point>(r#"{"x":1}"#).unwrap(), Point { x: 1 });
+    assert_eq!(from_str::<Point>(r#"{"y":[2],"x":1}"#).unwrap(), Point { x: 1 });
+    assert_eq!(from_value::<Point>(from_str(r#"{"x":1}"#).unwrap()).unwrap(),
+               Point { x: 1 });
+    test_parse_err::<Point>(vec![
+        ("{}", Error::Syntax(ErrorCode::MissingField("x"), 1, 2)),
+    ]);
+    assert!(from_str::<Point>(r#"{"x":-1}"#).is_err());
+}
+
 #[test]
 fn test_parse_option() {
     test_parse_ok(vec![
@@ -1207,6 +1596,338 @@ fn test_parse_trailing_whitespace() {
     ]);
 }
 
+#[test]
+fn test_parse_bom() {
+    let expected = Value::Object(treemap!("a".to_owned() => Value::U64(1)));
+
+    let v: Value = from_slice(b"\xEF\xBB\xBF{\"a\":1}").unwrap();
+    assert_eq!(v, expected);
+    let v: Value = serde_json::from_reader(&b"\xEF\xBB\xBF{\"a\":1}"[..]).unwrap();
+    assert_eq!(v, expected);
+    let v: Value = from_str("\u{FEFF}{\"a\":1}").unwrap();
+    assert_eq!(v, expected);
+
+    test_parse_slice_err::<Value>(vec![
+        (b"\xEF\xBB", Error::Syntax(ErrorCode::ExpectedSomeValue, 1, 2)),
+        (b"[\xEF\xBB\xBF1]", Error::Syntax(ErrorCode::ExpectedSomeValue, 1, 2)),
+        (b"\xEF\xBB\xBF\xEF\xBB\xBF1", Error::Syntax(ErrorCode::ExpectedSomeValue, 1, 4)),
+    ]);
+}
+
+#[test]
+fn test_parse_max_string_len() {
+    fn parse(s: &str, options: &ParseOptions) -> Vec<Result<Value, Error>> {
+        vec![from_str_with_options(s, options),
+             from_slice_with_options(s.as_bytes(), options),
+             from_reader_with_options(s.as_bytes(), options)]
+    }
+
+    let options = ParseOptions::new().max_string_len(4);
+    for json in &[r#""abcd""#, r#"["", "\u00e9\n"]"#, r#"{"abcd": "ab\"c"}"#] {
+        let expected: Value = from_str(json).unwrap();
+        for result in parse(json, &options) {
+            assert_eq!(result.unwrap(), expected);
+        }
+    }
+
+    for json in &[r#""abcde""#, r#"["\u00e9\u00e9x"]"#, r#"{"abcde": 1}"#,
+                  r#"["abcd\n"]"#] {
+        for result in parse(json, &options) {
+            match result {
+                Err(Error::Syntax(ErrorCode::StringTooLong, _, _)) => {}
+                other => panic!("{}: unexpected {:?}", json, other),
+            }
+        }
+    }
+
+    // A reader fails as soon as the limit is passed, before the string ends.
+    assert_eq!(from_reader_with_options::<_, Value>(&b"\"abcdefgh"[..], &options),
+               Err(Error::Syntax(ErrorCode::StringTooLong, 1, 6)));
+
+    // Without the option there is no limit.
+    let long = format!("\"{}\"", "x".repeat(10000));
+    assert!(from_str_with_options::<Value>(&long, &ParseOptions::new()).is_ok());
+}
+
+#[test]
+fn test_parse_max_elements() {
+    let options = ParseOptions::new().max_elements(5);
+
+    // Elements are counted across the whole document, not per container.
+    let json = r#"[1, {"a": [2]}, []]"#;
+    let expected: Value = from_str(json).unwrap();
+    assert_eq!(from_str_with_options::<Value>(json, &options).unwrap(), expected);
+    assert_eq!(from_slice_with_options::<Value>(json.as_bytes(), &options).unwrap(),
+               expected);
+
+    // The sixth element, `3`, fails before it is parsed, however long the
+    // rest of the document is.
+    let json = r#"[[1, 2], {"a": [3, 4]}, 5, 6, 7, 8, 9, 10"#;
+    assert_eq!(from_str_with_options::<Value>(json, &options),
+               Err(Error::Syntax(ErrorCode::TooManyElements, 1, 17)));
+    assert_eq!(from_reader_with_options::<_, Value>(json.as_bytes(), &options),
+               Err(Error::Syntax(ErrorCode::TooManyElements, 1, 17)));
+
+    let options = ParseOptions::new().max_elements(0);
+    assert_eq!(from_str_with_options::<Value>("[[], {}]", &options),
+               Err(Error::Syntax(ErrorCode::TooManyElements, 1, 2)));
+    assert_eq!(from_str_with_options::<Value>("[]", &options).unwrap(),
+               Value::Array(vec![]));
+}
+
+#[test]
+fn test_parse_options_reuse() {
+    let options = ParseOptions::new()
+        .allow_non_finite(true)
+        .allow_loose_numbers(true)
+        .max_string_len(3)
+        .max_elements(4);
+
+    let json = r#"[NaN, +1, "abc", .5]"#;
+    for value in vec![
+        options.from_str::<Value>(json).unwrap(),
+        options.from_slice::<Value>(json.as_bytes()).unwrap(),
+        options.from_reader::<_, Value>(json.as_bytes()).unwrap(),
+    ] {
+        let list = value.as_array().unwrap();
+        assert!(list[0].as_f64().unwrap().is_nan());
+        assert_eq!(list[1], Value::U64(1));
+        assert_eq!(list[2], Value::String("abc".to_owned()));
+        assert_eq!(list[3], Value::F64(0.5));
+    }
+
+    assert_eq!(options.from_str::<Value>(r#"["abcd"]"#),
+               Err(Error::Syntax(ErrorCode::StringTooLong, 1, 6)));
+    assert_eq!(options.from_str::<Value>("[1, 2, 3, 4, 5]"),
+               Err(Error::Syntax(ErrorCode::TooManyElements, 1, 13)));
+
+    // The default options are strict.
+    assert!(ParseOptions::new().from_str::<Value>(json).is_err());
+}
+
+#[test]
+fn test_comments_round_trip() {
+    use serde_json::comments;
+
+    let input = indoc!("
+        // Server settings
+        {
+          \"hosts\": [
+            \"a\", /* primary */
+            \"b\"
+            // more to come
+          ],
+          // The port to listen on.
+          \"port\": 80,
+          \"url\": \"http://x//y /* not a comment */\"
+        }
+        // end");
+    let (value, found) = comments::from_str(input).unwrap();
+    assert_eq!(value, from_str::<Value>(r#"{
+        "hosts": ["a", "b"],
+        "port": 80,
+        "url": "http://x//y /* not a comment */"
+    }"#).unwrap());
+    assert_eq!(found.leading(""), ["// Server settings"]);
+    assert_eq!(found.leading("/port"), ["// The port to listen on."]);
+    assert_eq!(found.leading("/hosts/1"), ["/* primary */"]);
+    assert_eq!(found.trailing("/hosts"), ["// more to come"]);
+    assert_eq!(found.end(), ["// end"]);
+    assert!(found.leading("/url").is_empty());
+
+    let expected = indoc!("
+        // Server settings
+        {
+          \"hosts\": [
+            \"a\",
+            /* primary */
+            \"b\"
+            // more to come
+          ],
+          // The port to listen on.
+          \"port\": 80,
+          \"url\": \"http://x//y /* not a comment */\"
+        }
+        // end");
+    let output = comments::to_string_pretty(&value, &found).unwrap();
+    assert_eq!(output, expected);
+
+    // Output in this layout comes back unchanged.
+    let (value, found) = comments::from_str(&output).unwrap();
+    assert_eq!(comments::to_string_pretty(&value, &found).unwrap(), output);
+
+    // Without comments the output matches `to_string_pretty`.
+    let (value, found) = comments::from_str(r#"{"a": [1, {}], "b": []}"#)
+        .unwrap();
+    assert!(found.is_empty());
+    assert_eq!(comments::to_string_pretty(&value, &found).unwrap(),
+               serde_json::to_string_pretty(&value).unwrap());
+
+    // Errors point into the original text.
+    assert_eq!(comments::from_str("{\n  // c\n  \"a\": }").unwrap_err(),
+               Error::Syntax(ErrorCode::ExpectedSomeValue, 3, 8));
+    assert!(comments::from_str("[1 /* open").is_err());
+}
+
+#[test]
+fn test_raw_value() {
+    use serde_json::RawValue;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Envelope {
+        id: u64,
+        payload: RawValue,
+    }
+
+    let input = r#"{"id": 7, "payload": { "z" : [1.50, 1e2,
+        123456789012345678901234567890], "a":"\u00e9" } }"#;
+    let raw_text = r#"{ "z" : [1.50, 1e2,
+        123456789012345678901234567890], "a":"\u00e9" }"#;
+    let expected = format!(r#"{{"id":7,"payload":{}}}"#, raw_text);
+
+    let envelope: Envelope = from_str(input).unwrap();
+    assert_eq!(envelope.payload.get(), raw_text);
+    assert_eq!(serde_json::to_string(&envelope).unwrap(), expected);
+
+    let envelope: Envelope = serde_json::from_reader(input.as_bytes()).unwrap();
+    assert_eq!(envelope.payload.get(), raw_text);
+    assert_eq!(serde_json::to_string(&envelope).unwrap(), expected);
+
+    let list: Vec<RawValue> = from_str(" [1 , \"two\",[ ], null ] ").unwrap();
+    let texts: Vec<&str> = list.iter().map(RawValue::get).collect();
+    assert_eq!(texts, vec!["1", "\"two\"", "[ ]", "null"]);
+
+    // Invalid JSON is still rejected.
+    assert!(from_str::<Envelope>(r#"{"id": 7, "payload": [1,]}"#).is_err());
+    assert!(from_str::<RawValue>("[1, 2").is_err());
+
+    let raw = RawValue::from_string(" {\"a\": 1} ".to_owned()).unwrap();
+    assert_eq!(raw.get(), " {\"a\": 1} ");
+    assert!(RawValue::from_string("{\"a\": }".to_owned()).is_err());
+
+    // Text handed over as a plain string by another deserializer is checked
+    // too, so that it cannot inject anything into the output.
+    {
+        use serde::de::Deserialize;
+        use serde::de::value::{self, ValueDeserializer};
+
+        let mut de = ValueDeserializer::<value::Error>::into_deserializer(
+            "1,\"x\":2".to_owned());
+        assert!(RawValue::deserialize(&mut de).is_err());
+        let mut de = ValueDeserializer::<value::Error>::into_deserializer(
+            "[1, \"x\"]".to_owned());
+        assert_eq!(RawValue::deserialize(&mut de).unwrap().get(), "[1, \"x\"]");
+    }
+
+    // Converting through `Value` parses the text.
+    let value = to_value(&envelope);
+    assert_eq!(value.pointer("/payload/a").unwrap(),
+               &Value::String("é".to_owned()));
+    let envelope: Envelope = from_value(value).unwrap();
+    assert!(!envelope.payload.get().contains(' '));
+    assert_eq!(from_str::<Value>(envelope.payload.get()).unwrap(),
+               from_str::<Value>(raw_text).unwrap());
+}
+
+#[test]
+fn test_from_ndjson() {
+    let input = "{\"a\": 1}\n[1, 2\n\n   \r\n\"three\"\r\nnull";
+    let results: Vec<_> = serde_json::from_ndjson(input.as_bytes()).collect();
+    assert_eq!(results.len(), 4);
+    assert_eq!(*results[0].as_ref().unwrap(),
+               Value::Object(treemap!("a".to_owned() => Value::U64(1))));
+    assert_eq!(*results[1].as_ref().unwrap_err(),
+               Error::Syntax(ErrorCode::EOFWhileParsingList, 2, 5));
+    assert_eq!(*results[2].as_ref().unwrap(), Value::String("three".to_owned()));
+    assert_eq!(*results[3].as_ref().unwrap(), Value::Null);
+
+    assert_eq!(serde_json::from_ndjson(&b""[..]).count(), 0);
+    assert_eq!(serde_json::from_ndjson(&b"\n \n"[..]).count(), 0);
+}
+
+#[test]
+fn test_to_ndjson() {
+    let values = vec![
+        from_str::<Value>(r#"{"a": [1, 2], "b": "x\ny"}"#).unwrap(),
+        Value::String("multi\nline\r\n".to_owned()),
+        Value::Null,
+    ];
+
+    let mut writer = Vec::new();
+    serde_json::to_ndjson(&mut writer, values.clone()).unwrap();
+    let ndjson = String::from_utf8(writer).unwrap();
+    assert_eq!(ndjson.lines().count(), 3);
+    assert!(ndjson.ends_with('\n'));
+
+    let parsed: Vec<Value> = serde_json::from_ndjson(ndjson.as_bytes())
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(parsed, values);
+
+    let mut writer = Vec::new();
+    serde_json::to_ndjson(&mut writer, &[1, 2]).unwrap();
+    assert_eq!(writer, b"1\n2\n");
+
+    // Line breaks in raw text are written as spaces.
+    let raw = serde_json::RawValue::from_string("{\"a\":\r\n  [1,\n2]}".to_owned())
+        .unwrap();
+    let mut writer = Vec::new();
+    serde_json::to_ndjson(&mut writer, &[&raw, &raw]).unwrap();
+    let ndjson = String::from_utf8(writer).unwrap();
+    assert_eq!(ndjson, "{\"a\":    [1, 2]}\n{\"a\":    [1, 2]}\n");
+    let parsed: Vec<Value> = serde_json::from_ndjson(ndjson.as_bytes())
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(parsed, vec![from_str::<Value>(raw.get()).unwrap(); 2]);
+}
+
+#[test]
+fn test_to_string_auto() {
+    // Four nodes: the object, the array and its two elements.
+    let value: Value = from_str(r#"{"a": [1, 2]}"#).unwrap();
+    assert_eq!(value.node_count(), 4);
+
+    assert_eq!(serde_json::to_string_auto(&value, 5).unwrap(),
+               serde_json::to_string_pretty(&value).unwrap());
+    assert_eq!(serde_json::to_string_auto(&value, 4).unwrap(),
+               r#"{"a":[1,2]}"#);
+    assert_eq!(serde_json::to_string_auto(&value, 0).unwrap(),
+               r#"{"a":[1,2]}"#);
+    assert_eq!(serde_json::to_string_auto(&Value::Null, 2).unwrap(), "null");
+}
+
+#[test]
+fn test_parse_all_errors() {
+    let input = indoc!("
+        {
+          \"a\": [1, tru, 3],
+          \"b\" 2,
+          \"c\": {\"d\": null}
+        }");
+    let (value, errors) = serde_json::parse_all_errors(input);
+    assert_eq!(value, Some(from_str(r#"{"a": [1, 3], "c": {"d": null}}"#).unwrap()));
+    assert_eq!(errors, vec![
+        Error::Syntax(ErrorCode::ExpectedSomeIdent, 2, 15),
+        Error::Syntax(ErrorCode::ExpectedColon, 3, 7),
+    ]);
+    assert_eq!(errors[0], from_str::<Value>(input).unwrap_err());
+
+    // When there is a single error, it is the one the strict parser reports.
+    for input in &["[1, }", "[1,]", "{\"a\":1,}", "[1x]", "[\"\\q\"]", "[[[",
+                   "{\"a\":1]", "[1, 2}", "[1,\n  x]", "1 2", "", "  nul "] {
+        let (_, errors) = serde_json::parse_all_errors(input);
+        assert_eq!(errors, vec![from_str::<Value>(input).unwrap_err()]);
+    }
+
+    let (value, errors) = serde_json::parse_all_errors(r#"{"a": [1, 2.5]}"#);
+    assert_eq!(value, Some(from_str(r#"{"a": [1, 2.5]}"#).unwrap()));
+    assert!(errors.is_empty());
+
+    let (value, errors) = serde_json::parse_all_errors("]");
+    assert_eq!(value, None);
+    assert_eq!(errors.len(), 1);
+}
+
 #[test]
 fn test_multiline_errors() {
     test_parse_err::<Map<String, String>>(vec![
@@ -1290,50 +2011,977 @@ fn test_lookup() {
 }
 
 #[test]
-fn test_serialize_seq_with_no_len() {
-    #[derive(Clone, Debug, PartialEq)]
-    struct MyVec<T>(Vec<T>);
+fn test_get() {
+    let mut obj: Value = serde_json::from_str(r#"{"x": [1, {"a": 2}], "y": 3}"#).unwrap();
+
+    assert_eq!(obj.get("y"), Some(&Value::U64(3)));
+    assert_eq!(obj.get(&"y".to_owned()), Some(&Value::U64(3)));
+    assert_eq!(obj.get("x").and_then(|x| x.get(1)).and_then(|x| x.get("a")),
+               Some(&Value::U64(2)));
+    assert_eq!(obj.get("z"), None);
+    assert_eq!(obj.get("x").unwrap().get(2), None);
+
+    // Indexing the wrong kind of container is not an error.
+    assert_eq!(obj.get(0), None);
+    assert_eq!(obj.get("x").unwrap().get("a"), None);
+    assert_eq!(obj.get("y").unwrap().get(0), None);
+    assert_eq!(obj.get("y").unwrap().get("a"), None);
+
+    *obj.get_mut("x").unwrap().get_mut(0).unwrap() = Value::Bool(true);
+    assert_eq!(obj.pointer("/x/0"), Some(&Value::Bool(true)));
+    assert!(obj.get_mut(0).is_none());
+}
 
-    impl<T> ser::Serialize for MyVec<T>
-        where T: ser::Serialize,
-    {
-        #[inline]
-        fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
-            where S: ser::Serializer,
-        {
-            let mut state = try!(serializer.serialize_seq(None));
-            for elem in &self.0 {
-                try!(serializer.serialize_seq_elt(&mut state, elem));
-            }
-            serializer.serialize_seq_end(state)
-        }
+#[test]
+fn test_entry() {
+    use serde_json::value::Entry;
+
+    let mut counts: Value = from_str(r#"{"b": 5}"#).unwrap();
+    for key in &["a", "b", "a"] {
+        let count = counts.entry(*key)
+            .unwrap()
+            .and_modify(|n| *n = Value::U64(n.as_u64().unwrap() + 1))
+            .or_insert(Value::U64(1));
+        assert!(count.as_u64().unwrap() > 0);
     }
+    assert_eq!(counts, from_str(r#"{"a": 2, "b": 6}"#).unwrap());
 
-    struct Visitor<T> {
-        marker: PhantomData<MyVec<T>>,
+    match counts.entry("c").unwrap() {
+        Entry::Vacant(entry) => assert_eq!(entry.key(), "c"),
+        Entry::Occupied(_) => panic!("expected a vacant entry"),
     }
+    counts.entry("c").unwrap().or_insert_with(|| Value::Null);
+    assert_eq!(counts.get("c"), Some(&Value::Null));
 
-    impl<T> de::Visitor for Visitor<T>
-        where T: de::Deserialize,
-    {
-        type Value = MyVec<T>;
+    assert!(Value::Array(vec![]).entry("a").is_none());
+    assert!(Value::Null.entry("a").is_none());
+}
 
-        #[inline]
-        fn visit_unit<E>(&mut self) -> Result<MyVec<T>, E>
-            where E: de::Error,
-        {
-            Ok(MyVec(Vec::new()))
-        }
+#[test]
+fn test_flatten() {
+    let v: Value = from_str(r#"{"a": {"b": [1, 2]}}"#).unwrap();
+    assert_eq!(v.flatten(), treemap!(
+        "/a/b/0".to_owned() => Value::U64(1),
+        "/a/b/1".to_owned() => Value::U64(2)
+    ));
 
-        #[inline]
-        fn visit_seq<V>(&mut self, mut visitor: V) -> Result<MyVec<T>, V::Error>
-            where V: de::SeqVisitor,
-        {
-            let mut values = Vec::new();
+    let v: Value = from_str(r#"{"x/y": {"~": null}, "e": [], "o": {}, "s": "t"}"#).unwrap();
+    assert_eq!(v.flatten(), treemap!(
+        "/e".to_owned() => Value::Array(vec![]),
+        "/o".to_owned() => Value::Object(treemap!()),
+        "/s".to_owned() => Value::String("t".to_owned()),
+        "/x~1y/~0".to_owned() => Value::Null
+    ));
+    for (pointer, leaf) in v.flatten() {
+        assert_eq!(v.pointer(&pointer), Some(&leaf));
+    }
 
-            while let Some(value) = try!(visitor.visit()) {
-                values.push(value);
-            }
+    assert_eq!(Value::Bool(true).flatten(), treemap!("".to_owned() => Value::Bool(true)));
+}
+
+#[test]
+fn test_unflatten() {
+    use serde_json::pointer::{PointerError, PointerSetError};
+
+    for json in &[
+        r#"{"a": {"b": [1, {"c": null}, [true]]}, "x~/y": "z", "e": [], "o": {}}"#,
+        r#"[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]"#,
+        r#""scalar""#,
+        r#"{"":{"":[]}}"#,
+    ] {
+        let v: Value = from_str(json).unwrap();
+        assert_eq!(Value::unflatten(&v.flatten()).unwrap(), v);
+    }
+
+    // Only dense indices become arrays.
+    let flat = treemap!(
+        "/a/0".to_owned() => Value::U64(0),
+        "/a/2".to_owned() => Value::U64(2)
+    );
+    assert_eq!(Value::unflatten(&flat).unwrap(),
+               from_str::<Value>(r#"{"a": {"0": 0, "2": 2}}"#).unwrap());
+
+    assert_eq!(Value::unflatten(&Map::new()).unwrap(), Value::Object(Map::new()));
+
+    let flat = treemap!(
+        "/a".to_owned() => Value::U64(1),
+        "/a/b".to_owned() => Value::U64(2)
+    );
+    assert_eq!(Value::unflatten(&flat), Err(PointerSetError::Conflict("/a/b".to_owned())));
+
+    let flat = treemap!(
+        "".to_owned() => Value::U64(1),
+        "/a".to_owned() => Value::U64(2)
+    );
+    assert_eq!(Value::unflatten(&flat), Err(PointerSetError::Conflict("/a".to_owned())));
+
+    let flat = treemap!("a".to_owned() => Value::U64(1));
+    assert_eq!(Value::unflatten(&flat),
+               Err(PointerSetError::Invalid("a".to_owned(), PointerError::MissingLeadingSlash)));
+}
+
+#[test]
+fn test_eq_unordered() {
+    let a: Value = from_str(r#"{"x": 1, "y": [{"p": true, "q": null}, 2]}"#).unwrap();
+    let b: Value = from_str(r#"{"y": [{"q": null, "p": true}, 2], "x": 1}"#).unwrap();
+    assert!(a.eq_unordered(&b));
+    assert!(b.eq_unordered(&a));
+
+    // Arrays stay order-sensitive.
+    let c: Value = from_str(r#"{"x": 1, "y": [2, {"p": true, "q": null}]}"#).unwrap();
+    assert!(!a.eq_unordered(&c));
+
+    let d: Value = from_str(r#"{"x": 1, "y": [{"p": true}, 2]}"#).unwrap();
+    assert!(!a.eq_unordered(&d));
+    assert!(!d.eq_unordered(&a));
+    assert!(!Value::U64(1).eq_unordered(&Value::String("1".to_owned())));
+}
+
+#[test]
+fn test_eq_ignoring() {
+    let a: Value = from_str(r#"{
+        "meta": {"timestamp": 1000, "id": "x"},
+        "items": [{"id": 1, "seen": 5}, {"id": 2, "seen": 6}]
+    }"#).unwrap();
+    let mut b = a.clone();
+    *b.pointer_mut("/meta/timestamp").unwrap() = Value::U64(2000);
+    assert!(a != b);
+    assert!(a.eq_ignoring(&b, &["/meta/timestamp"]));
+    assert!(a.eq_ignoring(&b, &["/meta"]));
+    assert!(!a.eq_ignoring(&b, &["/meta/id"]));
+    assert!(!a.eq_ignoring(&b, &[]));
+
+    // An ignored member may be missing on either side.
+    b.pointer_mut("/meta")
+        .unwrap()
+        .as_object_mut()
+        .unwrap()
+        .remove("timestamp");
+    assert!(a.eq_ignoring(&b, &["/meta/timestamp"]));
+    assert!(b.eq_ignoring(&a, &["/meta/timestamp"]));
+    assert!(!a.eq_ignoring(&b, &["/meta/id"]));
+
+    let mut c = a.clone();
+    *c.pointer_mut("/items/1/seen").unwrap() = Value::Null;
+    assert!(a.eq_ignoring(&c, &["/items/1/seen"]));
+    assert!(!a.eq_ignoring(&c, &["/items/0/seen"]));
+    c.pointer_mut("/items").unwrap().as_array_mut().unwrap().pop();
+    assert!(!a.eq_ignoring(&c, &["/items/1"]));
+
+    assert!(Value::U64(1).eq_ignoring(&Value::Null, &[""]));
+}
+
+#[test]
+fn test_container_constructors() {
+    let mut object = Value::object();
+    assert_eq!(object, Value::Object(Map::new()));
+    assert_eq!(object.insert("a", to_value(1)), Ok(None));
+    assert_eq!(object.to_string(), r#"{"a":1}"#);
+    assert_eq!(object.insert("a".to_owned(), Value::Null),
+               Ok(Some(Value::U64(1))));
+    assert_eq!(object.push(Value::Null), Err(Value::Null));
+
+    let mut array = Value::array();
+    assert_eq!(array, Value::Array(vec![]));
+    array.push(to_value(1)).unwrap();
+    array.push(Value::object()).unwrap();
+    assert_eq!(array.to_string(), "[1,{}]");
+    assert_eq!(array.insert("a", Value::Null), Err(Value::Null));
+
+    let mut object = Value::object_with_capacity(4);
+    assert_eq!(object, Value::object());
+    object.insert("b", Value::array()).unwrap();
+    assert_eq!(object.to_string(), r#"{"b":[]}"#);
+}
+
+#[test]
+fn test_merge_diff() {
+    // Applies a JSON Merge Patch as described in RFC 7386.
+    fn merge_patch(target: &mut Value, patch: &Value) {
+        let patch = match *patch {
+            Value::Object(ref patch) => patch,
+            _ => {
+                *target = patch.clone();
+                return;
+            }
+        };
+        if !target.is_object() {
+            *target = Value::Object(Map::new());
+        }
+        let map = target.as_object_mut().unwrap();
+        for (key, value) in patch {
+            if value.is_null() {
+                map.remove(key);
+            } else {
+                let mut member = map.remove(key).unwrap_or(Value::Null);
+                merge_patch(&mut member, value);
+                map.insert(key.clone(), member);
+            }
+        }
+    }
+
+    let cases = &[
+        (r#"{"a": 1, "b": {"c": 2, "d": 3}}"#, r#"{"a": 1, "b": {"c": 4}, "e": [5]}"#),
+        (r#"{"a": [1, 2], "b": "x"}"#, r#"{"a": [1], "b": {"c": true}}"#),
+        (r#"{"a": {"b": {"c": 1}}}"#, r#"{"a": {"b": {"c": 1, "d": 2}}}"#),
+        (r#"{"a": 1}"#, r#"{}"#),
+        (r#"{"a": 1}"#, r#"[1, 2]"#),
+        (r#"[1, 2]"#, r#"{"a": {"b": 1}}"#),
+        (r#""x""#, r#"3"#),
+    ];
+    for &(from, to) in cases {
+        let from: Value = from_str(from).unwrap();
+        let to: Value = from_str(to).unwrap();
+        let mut patched = from.clone();
+        merge_patch(&mut patched, &from.merge_diff(&to));
+        assert_eq!(patched, to);
+    }
+
+    let from: Value = from_str(r#"{"a": 1, "b": [1, 2], "c": {"d": 1, "e": 2}}"#).unwrap();
+    let to: Value = from_str(r#"{"a": 1, "b": [1, 3], "c": {"d": 1}}"#).unwrap();
+    assert_eq!(from.merge_diff(&to),
+               from_str::<Value>(r#"{"b": [1, 3], "c": {"e": null}}"#).unwrap());
+    assert_eq!(from.merge_diff(&from), Value::Object(Map::new()));
+}
+
+#[test]
+fn test_content_hash() {
+    let a: Value = from_str(r#"{"x": 1, "y": [true, null], "z": {"a": "b"}}"#)
+        .unwrap();
+    let b: Value = from_str(r#"{"z": {"a": "b"}, "y": [true, null], "x": 1}"#)
+        .unwrap();
+    assert_eq!(a.content_hash(), b.content_hash());
+    assert_eq!(a.content_hash(), a.clone().content_hash());
+
+    let mut c = a.clone();
+    *c.pointer_mut("/y/1").unwrap() = Value::Bool(false);
+    assert!(a.content_hash() != c.content_hash());
+
+    assert_eq!(Value::I64(1).content_hash(), Value::U64(1).content_hash());
+    assert!(Value::I64(-1).content_hash() != Value::U64(1).content_hash());
+    assert!(Value::U64(1).content_hash() != Value::F64(1.0).content_hash());
+    assert_eq!(Value::F64(0.0).content_hash(),
+               Value::F64(-0.0).content_hash());
+
+    // Element boundaries are part of the hash.
+    assert!(Value::Array(vec![Value::String("ab".to_owned())]).content_hash() !=
+            Value::Array(vec![Value::String("a".to_owned()),
+                              Value::String("b".to_owned())])
+                .content_hash());
+    assert!(Value::Array(vec![Value::Array(vec![])]).content_hash() !=
+            Value::Array(vec![]).content_hash());
+}
+
+#[test]
+fn test_walk() {
+    use serde_json::Map;
+    use serde_json::value::Visitor;
+
+    struct Recorder(Vec<String>);
+
+    impl Visitor for Recorder {
+        fn enter_array(&mut self, path: &str, array: &[Value]) {
+            self.0.push(format!("enter array {} ({})", path, array.len()));
+        }
+
+        fn leave_array(&mut self, path: &str, _array: &[Value]) {
+            self.0.push(format!("leave array {}", path));
+        }
+
+        fn enter_object(&mut self, path: &str, object: &Map<String, Value>) {
+            self.0.push(format!("enter object {} ({})", path, object.len()));
+        }
+
+        fn leave_object(&mut self, path: &str, _object: &Map<String, Value>) {
+            self.0.push(format!("leave object {}", path));
+        }
+
+        fn scalar(&mut self, path: &str, value: &Value) {
+            self.0.push(format!("scalar {} {}", path, value));
+        }
+    }
+
+    let value: Value = from_str(r#"{"a": [1, {"b/c": null}], "d": {}}"#)
+        .unwrap();
+    let mut recorder = Recorder(Vec::new());
+    value.walk(&mut recorder);
+    assert_eq!(recorder.0, vec![
+        "enter object  (2)",
+        "enter array /a (2)",
+        "scalar /a/0 1",
+        "enter object /a/1 (1)",
+        "scalar /a/1/b~1c null",
+        "leave object /a/1",
+        "leave array /a",
+        "enter object /d (0)",
+        "leave object /d",
+        "leave object ",
+    ]);
+
+    let mut recorder = Recorder(Vec::new());
+    Value::Bool(true).walk(&mut recorder);
+    assert_eq!(recorder.0, vec!["scalar  true"]);
+}
+
+#[test]
+fn test_take() {
+    let mut v: Value = from_str(r#"{"a": [1, 2], "b": true}"#).unwrap();
+
+    let a = v.get_mut("a").unwrap().take();
+    assert_eq!(a, Value::Array(vec![Value::U64(1), Value::U64(2)]));
+    assert_eq!(v, from_str(r#"{"a": null, "b": true}"#).unwrap());
+
+    let whole = v.take();
+    assert_eq!(whole, from_str(r#"{"a": null, "b": true}"#).unwrap());
+    assert_eq!(v, Value::Null);
+    assert_eq!(v.take(), Value::Null);
+}
+
+#[test]
+fn test_is_empty() {
+    assert!(Value::Null.is_empty());
+    assert!(Value::String(String::new()).is_empty());
+    assert!(!Value::String("a".to_owned()).is_empty());
+    assert!(Value::Array(vec![]).is_empty());
+    assert!(!Value::Array(vec![Value::Null]).is_empty());
+    assert!(Value::Object(Map::new()).is_empty());
+    assert!(!from_str::<Value>(r#"{"a": null}"#).unwrap().is_empty());
+    assert!(!Value::Bool(false).is_empty());
+    assert!(!Value::I64(0).is_empty());
+    assert!(!Value::U64(0).is_empty());
+    assert!(!Value::F64(0.0).is_empty());
+}
+
+#[test]
+fn test_len() {
+    let v: Value = from_str(r#"{"a": [1, 2, 3], "b": "hé", "c": {}}"#).unwrap();
+    assert_eq!(v.len(), Some(3));
+    assert_eq!(v.pointer("/a").unwrap().len(), Some(3));
+    assert_eq!(v.pointer("/b").unwrap().len(), Some(3));
+    assert_eq!(v.pointer("/c").unwrap().len(), Some(0));
+    assert_eq!(Value::Array(vec![]).len(), Some(0));
+    assert_eq!(Value::String(String::new()).len(), Some(0));
+
+    assert_eq!(Value::U64(12).len(), None);
+    assert_eq!(Value::F64(1.5).len(), None);
+    assert_eq!(Value::Bool(true).len(), None);
+    assert_eq!(Value::Null.len(), None);
+}
+
+#[test]
+fn test_try_into() {
+    assert_eq!(Value::String("a".to_owned()).try_into_string().unwrap(), "a");
+    assert_eq!(Value::Bool(true).try_into_bool().unwrap(), true);
+    assert_eq!(Value::I64(-5).try_into_i64().unwrap(), -5);
+    assert_eq!(Value::U64(5).try_into_i64().unwrap(), 5);
+    assert_eq!(Value::U64(5).try_into_u64().unwrap(), 5);
+    assert_eq!(Value::U64(5).try_into_f64().unwrap(), 5.0);
+    assert_eq!(Value::F64(0.5).try_into_f64().unwrap(), 0.5);
+
+    let err = Value::String("12".to_owned()).try_into_u64().unwrap_err();
+    assert_eq!(err.expected(), "u64");
+    assert_eq!(err.found(), "string");
+    assert_eq!(err.to_string(), "invalid type: expected u64, found string");
+    assert_eq!(err.into_value(), Value::String("12".to_owned()));
+
+    let err = Value::I64(-1).try_into_u64().unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: expected u64, found i64");
+    let err = Value::U64(u64::MAX).try_into_i64().unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: expected i64, found u64");
+    let err = Value::Null.try_into_bool().unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: expected boolean, found null");
+    let err = Value::Array(vec![]).try_into_string().unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: expected string, found array");
+    let err = Value::Object(Map::new()).try_into_f64().unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: expected f64, found object");
+}
+
+#[test]
+fn test_value_stats() {
+    let v: Value = from_str(r#"{"ab": [1, {"c": "xyz"}, []], "d": null}"#).unwrap();
+    assert_eq!(v.depth(), 3);
+    assert_eq!(v.node_count(), 7);
+    assert_eq!(v.string_bytes(), 7);
+
+    assert_eq!(Value::Null.depth(), 0);
+    assert_eq!(Value::Null.node_count(), 1);
+    assert_eq!(Value::Array(vec![]).depth(), 1);
+    assert_eq!(Value::Array(vec![]).node_count(), 1);
+    assert_eq!(Value::String("hé".to_owned()).string_bytes(), 3);
+}
+
+#[test]
+fn test_deep_size() {
+    let scalar_size = ::std::mem::size_of::<Value>();
+    assert_eq!(Value::Null.deep_size(), scalar_size);
+    assert_eq!(Value::U64(1).deep_size(), scalar_size);
+    assert_eq!(Value::Array(Vec::new()).deep_size(), scalar_size);
+
+    let small: Value = from_str(r#"{"a": [1]}"#).unwrap();
+    let large: Value = from_str(r#"{"a": [1, 2, 3], "bcd": "efghij"}"#).unwrap();
+    assert!(small.deep_size() > scalar_size);
+    assert!(large.deep_size() > small.deep_size());
+    assert!(Value::String("abc".to_owned()).deep_size() >= scalar_size + 3);
+}
+
+#[test]
+fn test_pointer_ext() {
+    let v: Value = from_str(r#"{"arr": [1, [2, 3]], "-1": true}"#).unwrap();
+    assert_eq!(v.pointer_ext("/arr/-1"), v.pointer("/arr/1"));
+    assert_eq!(v.pointer_ext("/arr/-1/-2"), Some(&Value::U64(2)));
+    assert_eq!(v.pointer_ext("/arr/-2"), Some(&Value::U64(1)));
+    assert_eq!(v.pointer_ext("/arr/0"), Some(&Value::U64(1)));
+    assert_eq!(v.pointer_ext("/-1"), Some(&Value::Bool(true)));
+    assert_eq!(v.pointer_ext(""), Some(&v));
+
+    assert_eq!(v.pointer_ext("/arr/-3"), None);
+    assert_eq!(v.pointer_ext("/arr/-0"), None);
+    assert_eq!(v.pointer_ext("/arr/-01"), None);
+    assert_eq!(v.pointer_ext("/arr/-"), None);
+    assert_eq!(v.pointer_ext("arr"), None);
+
+    // The standard pointer does not accept negative indices.
+    assert_eq!(v.pointer("/arr/-1"), None);
+}
+
+#[test]
+fn test_pointer_ci() {
+    let v: Value = from_str(r#"{"foo": 1, "bar": {"A~/b": [true]}, "Baz": 2, "baz": 3}"#).unwrap();
+    assert_eq!(v.pointer_ci("/Foo"), Some(&Value::U64(1)));
+    assert_eq!(v.pointer("/Foo"), None);
+    assert_eq!(v.pointer_ci("/BAR/a~0~1B/0"), Some(&Value::Bool(true)));
+
+    // An exact match wins over a case-insensitive one.
+    assert_eq!(v.pointer_ci("/baz"), Some(&Value::U64(3)));
+    assert_eq!(v.pointer_ci("/Baz"), Some(&Value::U64(2)));
+
+    assert_eq!(v.pointer_ci("/fo"), None);
+    assert_eq!(v.pointer_ci("/bar/a~0~1b/1"), None);
+    assert_eq!(v.pointer_ci("foo"), None);
+    assert_eq!(v.pointer_ci(""), Some(&v));
+}
+
+#[test]
+fn test_pointer_clone() {
+    let mut v: Value = from_str(r#"{"a": {"b": [1, 2]}}"#).unwrap();
+    let b = v.pointer_clone("/a/b").unwrap();
+    *v.pointer_mut("/a/b/0").unwrap() = Value::Null;
+    assert_eq!(b, Value::Array(vec![Value::U64(1), Value::U64(2)]));
+    assert_eq!(v.pointer("/a/b/0"), Some(&Value::Null));
+    assert_eq!(v.pointer_clone("/a/c"), None);
+}
+
+#[test]
+fn test_pointer_or() {
+    let v: Value = from_str(r#"{"a": {"b": [1, null]}}"#).unwrap();
+    let default = Value::Bool(false);
+    assert_eq!(v.pointer_or("/a/b/0", &default), &Value::U64(1));
+    assert_eq!(v.pointer_or("/a/b/1", &default), &Value::Null);
+    assert_eq!(v.pointer_or("/a/b/2", &default), &default);
+    assert_eq!(v.pointer_or("/a/c", &default), &default);
+    assert_eq!(v.pointer_or("a", &default), &default);
+    assert_eq!(v.pointer_or("", &default), &v);
+}
+
+#[test]
+fn test_pointer_mut() {
+    let mut v: Value = from_str(r#"{"a": [1, {"b~/c": 2}], "": 3}"#).unwrap();
+    *v.pointer_mut("/a/1/b~0~1c").unwrap() = Value::Bool(true);
+    *v.pointer_mut("/").unwrap() = Value::Null;
+    assert_eq!(v, from_str::<Value>(r#"{"a": [1, {"b~/c": true}], "": null}"#).unwrap());
+
+    assert!(v.pointer_mut("/a/2").is_none());
+    assert!(v.pointer_mut("/a/01").is_none());
+    assert!(v.pointer_mut("a").is_none());
+    v.pointer_mut("").unwrap().take();
+    assert_eq!(v, Value::Null);
+}
+
+#[test]
+fn test_pointer_set() {
+    let mut v: Value = from_str(r#"{"a": {"b": [1, {"c": 2}]}}"#).unwrap();
+    assert!(v.pointer_set("/a/b/1/c", Value::Bool(true)));
+    assert_eq!(v, from_str::<Value>(r#"{"a": {"b": [1, {"c": true}]}}"#).unwrap());
+
+    let before = v.clone();
+    assert!(!v.pointer_set("/a/d", Value::Null));
+    assert!(!v.pointer_set("/a/b/2", Value::Null));
+    assert!(!v.pointer_set("/a/b/0/c", Value::Null));
+    assert!(!v.pointer_set("a", Value::Null));
+    assert_eq!(v, before);
+
+    assert!(v.pointer_set("", Value::U64(1)));
+    assert_eq!(v, Value::U64(1));
+}
+
+#[test]
+fn test_pointer_mut_append() {
+    let mut v: Value = from_str(r#"{"arr": [1], "obj": {"-": 2}}"#).unwrap();
+    *v.pointer_mut("/arr/-").unwrap() = Value::U64(2);
+    assert_eq!(v.pointer("/arr"), Some(&from_str::<Value>("[1, 2]").unwrap()));
+    assert_eq!(v.pointer_mut("/arr/-"), Some(&mut Value::Null));
+    assert_eq!(v.pointer("/arr/2"), Some(&Value::Null));
+
+    // On an object, "-" is an ordinary key.
+    assert_eq!(v.pointer_mut("/obj/-"), Some(&mut Value::U64(2)));
+
+    // Only the last token can append, and nothing is appended otherwise.
+    let before = v.clone();
+    assert_eq!(v.pointer_mut("/arr/-/x"), None);
+    assert_eq!(v.pointer_parent_mut("/arr/-/x"), None);
+    assert_eq!(v, before);
+
+    assert!(v.pointer_set("/arr/-", Value::Bool(true)));
+    assert_eq!(v.pointer("/arr/3"), Some(&Value::Bool(true)));
+}
+
+#[test]
+fn test_pointer_parent_mut() {
+    let mut v: Value = from_str(r#"{"a": {"b": {"c": 1, "d": 2}}, "e": [3, 4], "x": 5}"#).unwrap();
+    {
+        let (parent, key) = v.pointer_parent_mut("/a/b/c").unwrap();
+        assert_eq!(key, "c");
+        assert_eq!(parent.as_object().unwrap().len(), 2);
+        parent.as_object_mut().unwrap().remove(&key);
+    }
+    assert_eq!(v.pointer("/a/b"), Some(&from_str::<Value>(r#"{"d": 2}"#).unwrap()));
+
+    {
+        let (parent, key) = v.pointer_parent_mut("/a/new~1key").unwrap();
+        assert_eq!(key, "new/key");
+        parent.as_object_mut().unwrap().insert(key, Value::Null);
+    }
+    assert_eq!(v.pointer("/a/new~1key"), Some(&Value::Null));
+
+    {
+        let (parent, index) = v.pointer_parent_mut("/e/1").unwrap();
+        assert_eq!(index, "1");
+        assert!(parent.is_array());
+    }
+
+    assert!(v.pointer_parent_mut("/x").is_some());
+    assert!(v.pointer_parent_mut("/x/y").is_none());
+    assert!(v.pointer_parent_mut("/missing/y").is_none());
+    assert!(v.pointer_parent_mut("").is_none());
+    assert!(v.pointer_parent_mut("a").is_none());
+}
+
+#[test]
+fn test_pointer_mut_many() {
+    use serde_json::pointer::PointerSetError;
+    use std::mem;
+
+    let mut v: Value = from_str(r#"{"a": [1, 2], "b": {"c": 3}}"#).unwrap();
+    {
+        let mut refs = v.pointer_mut_many(&["/a", "/b"]).unwrap();
+        let b = refs.pop().unwrap().unwrap();
+        let a = refs.pop().unwrap().unwrap();
+        mem::swap(a, b);
+    }
+    assert_eq!(v, from_str::<Value>(r#"{"a": {"c": 3}, "b": [1, 2]}"#).unwrap());
+
+    {
+        let refs = v.pointer_mut_many(&["/b/1", "/missing", "/b/0", "bad", "/a/c"]).unwrap();
+        let values: Vec<_> = refs.into_iter().map(|r| r.map(|v| v.clone())).collect();
+        assert_eq!(values, vec![Some(Value::U64(2)), None, Some(Value::U64(1)), None,
+                                Some(Value::U64(3))]);
+    }
+
+    assert_eq!(v.pointer_mut_many(&["/a", "/a"]).unwrap_err(),
+               PointerSetError::Conflict("/a".to_owned()));
+    assert_eq!(v.pointer_mut_many(&["/a/c", "/a"]).unwrap_err(),
+               PointerSetError::Conflict("/a".to_owned()));
+    assert_eq!(v.pointer_mut_many(&["/b", "", "/a"]).unwrap_err(),
+               PointerSetError::Conflict("".to_owned()));
+    assert!(v.pointer_mut_many(&["/ab", "/a"]).is_ok());
+    assert!(v.pointer_mut_many(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn test_project() {
+    let v: Value = from_str(r#"{
+        "a": {"b": {"x": 1}, "z": 2},
+        "c": [1, 2],
+        "d": "dropped",
+        "e": [{"f": 1, "g": 2}, {"f": 3}, {"g": 4}]
+    }"#).unwrap();
+
+    let expected: Value = from_str(r#"{"a": {"b": {"x": 1}}, "c": [1, 2]}"#).unwrap();
+    assert_eq!(v.project(&["/a/b", "/c"]), expected);
+
+    // Missing and malformed pointers are skipped.
+    assert_eq!(v.project(&["/a/b", "/c", "/a/missing", "/d/0", "nope"]), expected);
+
+    let expected: Value = from_str(r#"{"e": [{"f": 1}, {"f": 3}]}"#).unwrap();
+    assert_eq!(v.project(&["/e/0/f", "/e/1/f", "/e/2/f"]), expected);
+
+    assert_eq!(v.project(&[""]), v);
+    assert_eq!(v.project(&["/a/b", "/a"]).get("a"), v.get("a"));
+    assert_eq!(v.project(&[]), Value::Null);
+    assert_eq!(v.project(&["/missing"]), Value::Null);
+}
+
+#[test]
+fn test_retain() {
+    fn truthy(v: &Value) -> bool {
+        match *v {
+            Value::Null | Value::Bool(false) => false,
+            Value::I64(0) | Value::U64(0) => false,
+            Value::F64(f) => f != 0.0,
+            ref v => !v.is_empty(),
+        }
+    }
+
+    let mut v: Value = from_str(r#"{
+        "outer": {"a": 1, "b": 0, "c": "", "d": "x", "e": null, "f": [0], "g": false}
+    }"#).unwrap();
+    v.pointer_mut("/outer").unwrap().retain(|_, v| truthy(v));
+    assert_eq!(v, from_str::<Value>(r#"{"outer": {"a": 1, "d": "x", "f": [0]}}"#).unwrap());
+
+    v.pointer_mut("/outer").unwrap().retain(|k, _| k != "d");
+    assert_eq!(v, from_str::<Value>(r#"{"outer": {"a": 1, "f": [0]}}"#).unwrap());
+
+    let mut v: Value = from_str("[10, 11, 12, 13, 14]").unwrap();
+    let mut seen = Vec::new();
+    v.retain_elements(|i, v| {
+        seen.push(i);
+        i % 2 == 0 && *v != Value::U64(12)
+    });
+    assert_eq!(seen, vec![0, 1, 2, 3, 4]);
+    assert_eq!(v, from_str::<Value>("[10, 14]").unwrap());
+
+    // Both are no-ops on the other kinds of value.
+    v.retain(|_, _| false);
+    assert_eq!(v, from_str::<Value>("[10, 14]").unwrap());
+    let mut scalar = Value::U64(1);
+    scalar.retain(|_, _| false);
+    scalar.retain_elements(|_, _| false);
+    assert_eq!(scalar, Value::U64(1));
+}
+
+#[test]
+fn test_sort_keys() {
+    let mut v: Value = from_str(r#"{"b": [{"z": 1, "y": 2}], "a": {"d": 3, "c": 4}}"#).unwrap();
+    let before = v.clone();
+    v.sort_keys();
+    assert_eq!(serde_json::to_string(&v).unwrap(),
+               r#"{"a":{"c":4,"d":3},"b":[{"y":2,"z":1}]}"#);
+    assert!(v.eq_unordered(&before));
+
+    let mut scalar = Value::U64(1);
+    scalar.sort_keys();
+    assert_eq!(scalar, Value::U64(1));
+}
+
+#[test]
+fn test_map_scalars() {
+    let mut v: Value = from_str(r#"{
+        "user": "alice",
+        "password": "hunter2",
+        "tokens": ["a", {"id": 1, "secret": "b"}, []],
+        "active": true
+    }"#).unwrap();
+    v.map_scalars(|leaf| {
+        if leaf.is_string() {
+            *leaf = Value::String("***".to_owned());
+        }
+    });
+    let expected: Value = from_str(r#"{
+        "user": "***",
+        "password": "***",
+        "tokens": ["***", {"id": 1, "secret": "***"}, []],
+        "active": true
+    }"#).unwrap();
+    assert_eq!(v, expected);
+
+    let mut count = 0;
+    v.map_scalars(|_| count += 1);
+    assert_eq!(count, 6);
+
+    let mut scalar = Value::U64(1);
+    scalar.map_scalars(|leaf| *leaf = Value::Null);
+    assert_eq!(scalar, Value::Null);
+}
+
+#[test]
+fn test_negative_zero_eq() {
+    assert_eq!(Value::F64(-0.0), Value::F64(0.0));
+    assert_eq!(from_str::<Value>("-0.0").unwrap(), from_str::<Value>("0.0").unwrap());
+    assert_eq!(from_str::<Value>("[-0.0]").unwrap(), Value::Array(vec![Value::F64(0.0)]));
+    assert!(from_str::<Value>(r#"{"a": -0.0}"#).unwrap()
+        .eq_unordered(&from_str(r#"{"a": 0.0}"#).unwrap()));
+    assert_eq!(Value::F64(-0.0).total_cmp(&Value::F64(0.0)),
+               ::std::cmp::Ordering::Equal);
+}
+
+#[test]
+fn test_clone_eq() {
+    let mut v = Value::Null;
+    for i in 0..50 {
+        v = Value::Object(treemap!(
+            format!("k{}", i) => Value::Array(vec![v, Value::F64(i as f64)])
+        ));
+    }
+    let cloned = v.clone();
+    assert_eq!(cloned, v);
+    assert_eq!(cloned.as_object().unwrap().clone(), *v.as_object().unwrap());
+
+    let nan = Value::Array(vec![Value::F64(f64::NAN)]);
+    assert!(nan.clone() != nan);
+    assert!(Value::F64(f64::NAN) != Value::F64(f64::NAN));
+}
+
+#[test]
+fn test_integer_eq() {
+    assert_eq!(Value::I64(-5), Value::I64(-5));
+    assert!(Value::I64(-5) != Value::I64(5));
+    assert!(Value::I64(-5) != Value::U64(5));
+    assert!(Value::I64(-1) != Value::U64(u64::MAX));
+    assert_eq!(Value::I64(1), Value::U64(1));
+    assert_eq!(Value::U64(1), Value::I64(1));
+    assert_eq!(Value::I64(i64::MAX), Value::U64(i64::MAX as u64));
+    assert!(Value::I64(1) != Value::F64(1.0));
+
+    assert_eq!(from_str::<Value>("-0").unwrap(), Value::U64(0));
+    assert_eq!(from_str::<Value>("[-0, 7]").unwrap(),
+               Value::Array(vec![Value::I64(0), Value::I64(7)]));
+    assert_eq!(from_str::<Value>("-5").unwrap(), Value::I64(-5));
+}
+
+#[test]
+fn test_float_integer_distinction_round_trip() {
+    let value: Value = from_str("[1, 1.0, -1, -1.0, 1e2]").unwrap();
+    let round_tripped: Value = from_str(&value.to_string()).unwrap();
+    for v in &[&value, &round_tripped] {
+        let v = v.as_array().unwrap();
+        assert!(v[0].is_u64());
+        assert!(v[1].is_f64());
+        assert!(v[2].is_i64());
+        assert!(v[3].is_f64());
+        assert!(v[4].is_f64());
+    }
+    assert_eq!(round_tripped, value);
+}
+
+#[test]
+fn test_json_macro() {
+    assert_eq!(json!(null), Value::Null);
+    assert_eq!(json!([]), Value::Array(vec![]));
+    assert_eq!(json!({}), Value::Object(Map::new()));
+    assert_eq!(json!(-1), Value::I64(-1));
+    assert_eq!(json!("a"), Value::String("a".to_owned()));
+
+    let value = json!({
+        "a": [1, 2, {"b": true}],
+        "c": null,
+        "d": {"e": [null, [], -1.5]},
+    });
+    let expected: Value = from_str(r#"{
+        "a": [1, 2, {"b": true}],
+        "c": null,
+        "d": {"e": [null, [], -1.5]}
+    }"#).unwrap();
+    assert_eq!(value, expected);
+
+    let x = 5;
+    let name = "n".to_owned();
+    let list = vec![true, false];
+    let value = json!({
+        (name.clone()): x * 2,
+        "list": list,
+        "inner": [x, {"y": x + 1}, name],
+    });
+    let expected: Value = from_str(r#"{
+        "n": 10,
+        "list": [true, false],
+        "inner": [5, {"y": 6}, "n"]
+    }"#).unwrap();
+    assert_eq!(value, expected);
+}
+
+#[test]
+fn test_coerce_str() {
+    let coerced = |s: &str| from_str::<Value>(s).unwrap().coerce_str();
+    assert_eq!(coerced("true"), Some("true".to_owned()));
+    assert_eq!(coerced("42"), Some("42".to_owned()));
+    assert_eq!(coerced("-7"), Some("-7".to_owned()));
+    assert_eq!(coerced("1.5"), Some("1.5".to_owned()));
+    assert_eq!(coerced(r#""x""#), Some("x".to_owned()));
+    assert_eq!(coerced(r#""a\"b""#), Some("a\"b".to_owned()));
+    assert_eq!(coerced("null"), None);
+    assert_eq!(coerced("[1]"), None);
+    assert_eq!(coerced(r#"{"a": 1}"#), None);
+}
+
+#[test]
+fn test_prune_nulls() {
+    let mut value: Value = from_str(r#"{"a":null,"b":{"c":null,"d":1}}"#)
+        .unwrap();
+    value.prune_nulls(false);
+    assert_eq!(value, from_str::<Value>(r#"{"b":{"d":1}}"#).unwrap());
+
+    let original: Value = from_str(r#"[null, {"a": [null, {"b": null}]}, 2]"#)
+        .unwrap();
+    let mut value = original.clone();
+    value.prune_nulls(false);
+    assert_eq!(value,
+               from_str::<Value>(r#"[null, {"a": [null, {}]}, 2]"#).unwrap());
+    let mut value = original.clone();
+    value.prune_nulls(true);
+    assert_eq!(value, from_str::<Value>(r#"[{"a": [{}]}, 2]"#).unwrap());
+
+    let mut value = Value::Null;
+    value.prune_nulls(true);
+    assert_eq!(value, Value::Null);
+}
+
+#[test]
+fn test_into_pairs() {
+    let value: Value = from_str(r#"{"b": [true], "a": 1, "c": null}"#).unwrap();
+    let expected: Vec<(String, Value)> = value.as_object()
+        .unwrap()
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    let pairs = value.into_pairs().unwrap();
+    assert_eq!(pairs, expected);
+    assert_eq!(pairs.len(), 3);
+
+    assert_eq!(Value::Object(Map::new()).into_pairs(), Some(vec![]));
+    assert_eq!(Value::Null.into_pairs(), None);
+    assert_eq!(Value::Array(vec![]).into_pairs(), None);
+    assert_eq!(Value::String("a".to_owned()).into_pairs(), None);
+}
+
+#[test]
+fn test_as_mut() {
+    let mut value: Value = from_str(r#"{"list": [1], "obj": {}}"#).unwrap();
+    value.pointer_mut("/list")
+        .unwrap()
+        .as_array_mut()
+        .unwrap()
+        .push(Value::U64(2));
+    value.pointer_mut("/obj")
+        .unwrap()
+        .as_object_mut()
+        .unwrap()
+        .insert("k".to_owned(), Value::Bool(true));
+    assert_eq!(value.to_string(), r#"{"list":[1,2],"obj":{"k":true}}"#);
+
+    assert!(value.as_array_mut().is_none());
+    assert!(Value::Null.as_object_mut().is_none());
+}
+
+#[test]
+fn test_object_insertion_order() {
+    let mut value: Value = from_str(r#"{"b": 1, "d": 2}"#).unwrap();
+    {
+        let map = value.as_object_mut().unwrap();
+        map.insert("c".to_owned(), Value::U64(3));
+        map.insert("a".to_owned(), Value::U64(4));
+        map.insert("d".to_owned(), Value::U64(5));
+    }
+    // Keys are sorted unless the `preserve_order` feature is enabled.
+    assert_eq!(value.to_string(), r#"{"a":4,"b":1,"c":3,"d":5}"#);
+}
+
+#[test]
+fn test_value_ordering() {
+    let sorted = vec![
+        Value::Null,
+        Value::Bool(false),
+        Value::Bool(true),
+        Value::F64(f64::NEG_INFINITY),
+        Value::I64(-3),
+        Value::F64(-2.5),
+        Value::U64(0),
+        Value::F64(0.5),
+        Value::I64(1),
+        Value::F64(1.0),
+        Value::U64(u64::MAX),
+        Value::F64(f64::NAN),
+        Value::String("".to_owned()),
+        Value::String("a".to_owned()),
+        Value::String("b".to_owned()),
+        Value::Array(vec![]),
+        Value::Array(vec![Value::U64(1)]),
+        Value::Array(vec![Value::U64(1), Value::Null]),
+        Value::Array(vec![Value::U64(2)]),
+        Value::Object(treemap!()),
+        Value::Object(treemap!("a".to_owned() => Value::U64(2))),
+        Value::Object(treemap!("b".to_owned() => Value::U64(1))),
+    ];
+
+    let mut values = sorted.clone();
+    values.reverse();
+    values.sort_by(Value::total_cmp);
+    assert_eq!(format!("{:?}", values), format!("{:?}", sorted));
+
+    let mut values = sorted.clone();
+    values.swap(0, 7);
+    values.swap(3, 18);
+    values.sort_by(Value::total_cmp);
+    assert_eq!(format!("{:?}", values), format!("{:?}", sorted));
+
+    assert!(Value::I64(-1) < Value::U64(0));
+    assert_eq!(Value::I64(1).partial_cmp(&Value::U64(1)),
+               Some(::std::cmp::Ordering::Equal));
+    assert_eq!(Value::I64(1).total_cmp(&Value::U64(1)),
+               ::std::cmp::Ordering::Equal);
+    assert!(Value::U64(9007199254740993) > Value::F64(9007199254740992.0));
+    assert!(Value::F64(9007199254740992.0) < Value::U64(9007199254740993));
+    assert!(Value::U64(2) > Value::F64(1.5));
+    assert!(Value::String("1".to_owned()) > Value::U64(2));
+    assert_eq!(Value::F64(0.0).partial_cmp(&Value::F64(-0.0)),
+               Some(::std::cmp::Ordering::Equal));
+    assert_eq!(Value::F64(f64::NAN).partial_cmp(&Value::U64(1)), None);
+}
+
+#[test]
+fn test_serialize_seq_with_no_len() {
+    #[derive(Clone, Debug, PartialEq)]
+    struct MyVec<T>(Vec<T>);
+
+    impl<T> ser::Serialize for MyVec<T>
+        where T: ser::Serialize,
+    {
+        #[inline]
+        fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+            where S: ser::Serializer,
+        {
+            let mut state = try!(serializer.serialize_seq(None));
+            for elem in &self.0 {
+                try!(serializer.serialize_seq_elt(&mut state, elem));
+            }
+            serializer.serialize_seq_end(state)
+        }
+    }
+
+    struct Visitor<T> {
+        marker: PhantomData<MyVec<T>>,
+    }
+
+    impl<T> de::Visitor for Visitor<T>
+        where T: de::Deserialize,
+    {
+        type Value = MyVec<T>;
+
+        #[inline]
+        fn visit_unit<E>(&mut self) -> Result<MyVec<T>, E>
+            where E: de::Error,
+        {
+            Ok(MyVec(Vec::new()))
+        }
+
+        #[inline]
+        fn visit_seq<V>(&mut self, mut visitor: V) -> Result<MyVec<T>, V::Error>
+            where V: de::SeqVisitor,
+        {
+            let mut values = Vec::new();
+
+            while let Some(value) = try!(visitor.visit()) {
+                values.push(value);
+            }
 
             try!(visitor.end());
 
@@ -1507,10 +3155,23 @@ fn test_serialize_rejects_non_key_maps() {
         3 => 4
     );
 
-    match serde_json::to_vec(&map).unwrap_err() {
-        serde_json::Error::Syntax(serde_json::ErrorCode::KeyMustBeAString, 0, 0) => {}
-        _ => panic!("integers used as keys"),
-    }
+    assert_eq!(serde_json::to_vec(&map).unwrap_err(),
+               Error::Syntax(ErrorCode::KeyMustBeAString, 0, 0));
+}
+
+#[test]
+fn test_error_eq() {
+    use std::io;
+
+    assert_eq!(from_str::<Value>("[1,"),
+               Err(Error::Syntax(ErrorCode::EOFWhileParsingValue, 1, 3)));
+    assert!(Error::Syntax(ErrorCode::ExpectedColon, 1, 2) !=
+            Error::Syntax(ErrorCode::ExpectedColon, 1, 3));
+
+    let not_found = Error::Io(io::Error::new(io::ErrorKind::NotFound, "a"));
+    assert_eq!(not_found, Error::Io(io::Error::new(io::ErrorKind::NotFound, "b")));
+    assert!(not_found != Error::Io(io::Error::new(io::ErrorKind::Other, "a")));
+    assert!(not_found != Error::Syntax(ErrorCode::ExpectedColon, 0, 0));
 }
 
 #[test]
@@ -1597,6 +3258,84 @@ fn test_json_stream_empty() {
     assert!(parsed.next().is_none());
 }
 
+#[test]
+fn test_event_reader() {
+    use std::io::Read;
+    use serde_json::de::{Event, EventReader};
+
+    let stream = r#" {"a": [1, -2, 3.5, [], {}], "b": {"c": null, "d": true}, "e": "f"} "#;
+    let events: Vec<Event> = EventReader::new(stream.as_bytes().bytes())
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(events, vec![
+        Event::StartObject,
+        Event::Key("a".to_owned()),
+        Event::StartArray,
+        Event::U64(1),
+        Event::I64(-2),
+        Event::F64(3.5),
+        Event::StartArray,
+        Event::EndArray,
+        Event::StartObject,
+        Event::EndObject,
+        Event::EndArray,
+        Event::Key("b".to_owned()),
+        Event::StartObject,
+        Event::Key("c".to_owned()),
+        Event::Null,
+        Event::Key("d".to_owned()),
+        Event::Bool(true),
+        Event::EndObject,
+        Event::Key("e".to_owned()),
+        Event::String("f".to_owned()),
+        Event::EndObject,
+    ]);
+
+    let events: Vec<_> = EventReader::new("\"x\"".bytes().map(Ok)).collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(*events[0].as_ref().unwrap(), Event::String("x".to_owned()));
+
+    for &(stream, ref err) in &[
+        ("[1 2]", Error::Syntax(ErrorCode::ExpectedListCommaOrEnd, 1, 4)),
+        ("[1,]", Error::Syntax(ErrorCode::ExpectedSomeValue, 1, 4)),
+        ("{1:2}", Error::Syntax(ErrorCode::KeyMustBeAString, 1, 2)),
+        ("{\"a\" 1}", Error::Syntax(ErrorCode::ExpectedColon, 1, 6)),
+        ("{\"a\":1", Error::Syntax(ErrorCode::EOFWhileParsingObject, 1, 6)),
+        ("[1]]", Error::Syntax(ErrorCode::TrailingCharacters, 1, 4)),
+    ] {
+        let mut reader = EventReader::new(stream.bytes().map(Ok));
+        let actual = reader.find(|event| event.is_err()).unwrap().unwrap_err();
+        assert_eq!(actual, *err, "{:?}", stream);
+        assert!(reader.next().is_none());
+    }
+
+    let events: Vec<Event> = EventReader::from_reader(&b"\xEF\xBB\xBF[true]"[..])
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(events, vec![Event::StartArray, Event::Bool(true), Event::EndArray]);
+
+    let options = ParseOptions::new().allow_non_finite(true);
+    let events: Vec<Event> = EventReader::with_options("[NaN]".bytes().map(Ok), &options)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(events.len(), 3);
+    assert!(events[1] != events[1]);
+
+    // The options fail a stream where they would fail the whole value.
+    for &(stream, ref options) in &[
+        ("[NaN]", ParseOptions::new()),
+        ("[[1]]", ParseOptions::new().max_depth(1)),
+        ("[1,[2]]", ParseOptions::new().max_elements(2)),
+        ("{\"a\":1,\"b\":2}", ParseOptions::new().max_elements(1)),
+    ] {
+        let err = from_str_with_options::<Value>(stream, options).unwrap_err();
+        let mut reader = EventReader::with_options(stream.bytes().map(Ok), options);
+        let actual = reader.find(|event| event.is_err()).unwrap().unwrap_err();
+        assert_eq!(actual, err, "{:?}", stream);
+        assert!(reader.next().is_none());
+    }
+}
+
 #[test]
 fn test_json_pointer() {
     // Test case taken from https://tools.ietf.org/html/rfc6901#page-5
@@ -1634,6 +3373,309 @@ fn test_json_pointer() {
     assert!(data.pointer("/foo/01").is_none());
 }
 
+#[test]
+fn test_pointer_empty_key() {
+    let mut data: Value = from_str(r#"{
+        "": {"": 1, "b": 2},
+        "a": {"": 3, "c": {"": [4]}},
+        "d": [5]
+    }"#).unwrap();
+    assert!(data.pointer("/").unwrap().is_object());
+    assert_eq!(data.pointer("//").unwrap(), &Value::U64(1));
+    assert_eq!(data.pointer("//b").unwrap(), &Value::U64(2));
+    assert_eq!(data.pointer("/a/").unwrap(), &Value::U64(3));
+    assert_eq!(data.pointer("/a/c//0").unwrap(), &Value::U64(4));
+    // An empty token is not an array index.
+    assert!(data.pointer("/d/").is_none());
+    assert!(data.pointer("/a/c/").unwrap().is_array());
+    assert!(data.pointer("/a//").is_none());
+
+    assert_eq!(data.pointer_ext("/a/").unwrap(), &Value::U64(3));
+    assert_eq!(data.pointer_all("/*/"), vec![&Value::U64(1), &Value::U64(3)]);
+    *data.pointer_mut("/a/").unwrap() = Value::U64(6);
+    assert_eq!(data.pointer("/a/").unwrap(), &Value::U64(6));
+}
+
+#[test]
+fn test_pointer_mixed_escapes() {
+    let data: Value = serde_json::from_str(r#"{
+        "a": {"b": {"c~1": {"d/e": [10, 20]}}},
+        "~1": 1,
+        "~": 2
+    }"#).unwrap();
+    assert_eq!(data.pointer("/a/b/c~01/d~1e/1").unwrap(), &Value::U64(20));
+    assert_eq!(data.pointer("/~01").unwrap(), &Value::U64(1));
+    assert_eq!(data.pointer("/~0").unwrap(), &Value::U64(2));
+    assert!(data.pointer("/a/b/c~1").is_none());
+    assert!(data.pointer("/a/b/c~01/d/e").is_none());
+}
+
+// That the tokens without escapes are borrowed rather than copied is checked
+// by `test_pointer_tokens_borrow_the_pointer` in value.rs.
+#[test]
+fn test_pointer_variants_mixed_escapes() {
+    let mut data: Value = serde_json::from_str(r#"{
+        "a": {"B~1": {"c/d": [10, 20]}}
+    }"#).unwrap();
+    assert_eq!(data.pointer_ext("/a/B~01/c~1d/-1").unwrap(), &Value::U64(20));
+    assert_eq!(data.pointer_ci("/A/b~01/C~1D/0").unwrap(), &Value::U64(10));
+    *data.pointer_mut("/a/B~01/c~1d/0").unwrap() = Value::U64(30);
+    assert_eq!(data.pointer("/a/B~01/c~1d/0").unwrap(), &Value::U64(30));
+    {
+        let (_, key) = data.pointer_parent_mut("/a/B~01").unwrap();
+        assert_eq!(key, "B~1");
+    }
+    assert!(data.pointer_mut("/a/B~1/c~1d").is_none());
+}
+
+#[test]
+fn test_parse_pointer() {
+    use serde_json::pointer::{PointerError, parse_pointer};
+
+    assert_eq!(parse_pointer("/a~1b/0").unwrap(), vec!["a/b", "0"]);
+    assert_eq!(parse_pointer("").unwrap(), Vec::<String>::new());
+    assert_eq!(parse_pointer("/").unwrap(), vec![""]);
+    assert_eq!(parse_pointer("//").unwrap(), vec!["", ""]);
+    assert_eq!(parse_pointer("/m~0n/~01").unwrap(), vec!["m~n", "~1"]);
+
+    assert_eq!(parse_pointer("abc"), Err(PointerError::MissingLeadingSlash));
+    assert_eq!(parse_pointer("a/b"), Err(PointerError::MissingLeadingSlash));
+    assert_eq!(parse_pointer("/a~2"), Err(PointerError::InvalidEscape));
+    assert_eq!(parse_pointer("/a~"), Err(PointerError::InvalidEscape));
+}
+
+#[test]
+fn test_validate_pointer() {
+    use serde_json::pointer::{PointerError, is_valid_pointer, validate_pointer};
+
+    for pointer in &["", "/", "/a~0b~1c", "/~01", "//0", "/\u{e9}~1"] {
+        assert_eq!(validate_pointer(pointer), Ok(()));
+        assert!(is_valid_pointer(pointer));
+    }
+
+    assert_eq!(validate_pointer("/a~2b"), Err(PointerError::InvalidEscape));
+    assert_eq!(validate_pointer("/a~"), Err(PointerError::InvalidEscape));
+    assert_eq!(validate_pointer("/~~0"), Err(PointerError::InvalidEscape));
+    assert_eq!(validate_pointer("a/b"), Err(PointerError::MissingLeadingSlash));
+    assert!(!is_valid_pointer("/a~2b"));
+    assert!(!is_valid_pointer("~0"));
+}
+
+#[test]
+fn test_pointer_tokens() {
+    use serde_json::pointer::parse_pointer;
+
+    let docs: Vec<Value> = vec![
+        serde_json::from_str(r#"{"a/b": {"m~n": [1, 2]}}"#).unwrap(),
+        serde_json::from_str(r#"{"a/b": {"m~n": [3]}}"#).unwrap(),
+        serde_json::from_str(r#"{"a/b": 4}"#).unwrap(),
+        serde_json::from_str(r#"[5]"#).unwrap(),
+    ];
+    for pointer in &["", "/a~1b", "/a~1b/m~0n", "/a~1b/m~0n/1", "/0", "/00"] {
+        let tokens = parse_pointer(pointer).unwrap();
+        for doc in &docs {
+            assert_eq!(doc.pointer_tokens(&tokens), doc.pointer(pointer));
+        }
+    }
+    let empty: &[&str] = &[];
+    assert_eq!(docs[2].pointer_tokens(empty), Some(&docs[2]));
+}
+
+#[test]
+fn test_pointer_strict() {
+    use serde_json::pointer::PointerError;
+
+    let data: Value = from_str(r#"{"a~2": 1, "a": {"b/c": [true]}}"#).unwrap();
+    assert_eq!(data.pointer_strict("/a/b~1c/0"), Ok(Some(&Value::Bool(true))));
+    assert_eq!(data.pointer_strict(""), Ok(Some(&data)));
+    assert_eq!(data.pointer_strict("/a/x"), Ok(None));
+    assert_eq!(data.pointer_strict("/a/b~1c/1"), Ok(None));
+
+    assert_eq!(data.pointer_strict("/a~2"), Err(PointerError::InvalidEscape));
+    assert_eq!(data.pointer_strict("/a/~"), Err(PointerError::InvalidEscape));
+    assert_eq!(data.pointer_strict("a"),
+               Err(PointerError::MissingLeadingSlash));
+
+    // The lenient lookup keeps treating a malformed escape as literal text.
+    assert_eq!(data.pointer("/a~2"), Some(&Value::U64(1)));
+}
+
+#[test]
+fn test_pointer_all() {
+    let data: Value = from_str(r#"{
+        "orders": [
+            {"items": [{"price": 1}, {"price": 2}]},
+            {"items": []},
+            {"items": [{"price": 3}, {"sku": "x"}]},
+            {"total": 0}
+        ],
+        "*": "star"
+    }"#).unwrap();
+    assert_eq!(data.pointer_all("/orders/*/items/*/price"),
+               vec![&Value::U64(1), &Value::U64(2), &Value::U64(3)]);
+    assert_eq!(data.pointer_all("/orders/2/items/*/sku"),
+               vec![&Value::String("x".to_owned())]);
+    assert_eq!(data.pointer_all("/orders/*/total"), vec![&Value::U64(0)]);
+    assert_eq!(data.pointer_all("/orders/*").len(), 4);
+    assert_eq!(data.pointer_all("/*").len(), 2);
+    assert_eq!(data.pointer_all(""), vec![&data]);
+    assert!(data.pointer_all("/orders/*/missing").is_empty());
+    assert!(data.pointer_all("/*/*/*/*/*/*").is_empty());
+    assert!(data.pointer_all("orders").is_empty());
+}
+
+#[test]
+fn test_pointer_all_recursive() {
+    let data: Value = from_str(r#"{
+        "id": 1,
+        "a": {"id": 2, "b": [{"id": 3}, {"c": {"id": 4}}]},
+        "d": [5, {"x": {"y": {"id": 6}}}]
+    }"#).unwrap();
+    let ids: Vec<u64> = data.pointer_all("/**/id")
+        .into_iter()
+        .map(|v| v.as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 2, 3, 4, 6]);
+
+    // Overlapping `**` expansions do not produce duplicates.
+    assert_eq!(data.pointer_all("/**/**/id").len(), 5);
+    assert_eq!(data.pointer_all("/**").len(), data.node_count());
+    assert_eq!(data.pointer_all("/**/**").len(), data.node_count());
+
+    let ids: Vec<u64> = data.pointer_all("/a/**/id")
+        .into_iter()
+        .map(|v| v.as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![2, 3, 4]);
+    assert_eq!(data.pointer_all("/d/**/y/id"), vec![&Value::U64(6)]);
+    assert!(data.pointer_all("/**/missing").is_empty());
+}
+
+#[test]
+fn test_pointer_all_paths() {
+    let data: Value = from_str(r#"{
+        "items": [{"name": "a"}, {"price": 2}, {"name": "c"}],
+        "a/b": {"m~n": true}
+    }"#).unwrap();
+
+    let names = data.pointer_all_paths("/items/*/name");
+    assert_eq!(names,
+               vec![("/items/0/name".to_owned(),
+                     &Value::String("a".to_owned())),
+                    ("/items/2/name".to_owned(),
+                     &Value::String("c".to_owned()))]);
+    for (path, value) in names {
+        assert_eq!(data.pointer(&path), Some(value));
+    }
+
+    // Keys are escaped in the returned pointers.
+    assert_eq!(data.pointer_all_paths("/*/*"),
+               vec![("/a~1b/m~0n".to_owned(), &Value::Bool(true)),
+                    ("/items/0".to_owned(), data.pointer("/items/0").unwrap()),
+                    ("/items/1".to_owned(), data.pointer("/items/1").unwrap()),
+                    ("/items/2".to_owned(), data.pointer("/items/2").unwrap())]);
+    assert_eq!(data.pointer_all_paths("/a~1b/m~0n"),
+               vec![("/a~1b/m~0n".to_owned(), &Value::Bool(true))]);
+
+    let prices: Vec<String> = data.pointer_all_paths("/**/price")
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect();
+    assert_eq!(prices, vec!["/items/1/price".to_owned()]);
+
+    let all = data.pointer_all_paths("/**");
+    assert_eq!(all.len(), data.node_count());
+    for (path, value) in all {
+        assert_eq!(data.pointer(&path), Some(value));
+    }
+    assert_eq!(data.pointer_all_paths(""), vec![("".to_owned(), &data)]);
+}
+
+#[test]
+fn test_jsonpath_bookstore() {
+    use serde_json::jsonpath::{PathError, query};
+
+    let store: Value = from_str(r#"{
+        "store": {
+            "book": [
+                {"category": "reference", "author": "Nigel Rees",
+                 "title": "Sayings of the Century", "price": 8.95},
+                {"category": "fiction", "author": "Evelyn Waugh",
+                 "title": "Sword of Honour", "price": 12.99},
+                {"category": "fiction", "author": "Herman Melville",
+                 "title": "Moby Dick", "isbn": "0-553-21311-3",
+                 "price": 8.99},
+                {"category": "fiction", "author": "J. R. R. Tolkien",
+                 "title": "The Lord of the Rings", "isbn": "0-395-19395-8",
+                 "price": 22.99}
+            ],
+            "bicycle": {"color": "red", "price": 19.95}
+        }
+    }"#).unwrap();
+
+    let strings = |path: &str| -> Vec<String> {
+        query(&store, path)
+            .unwrap()
+            .into_iter()
+            .map(|v| v.as_str().unwrap().to_owned())
+            .collect()
+    };
+    let authors = vec!["Nigel Rees", "Evelyn Waugh", "Herman Melville",
+                       "J. R. R. Tolkien"];
+
+    assert_eq!(strings("$.store.book[*].author"), authors);
+    assert_eq!(strings("$['store']['book'][*]['author']"), authors);
+    assert_eq!(strings("$..author"), authors);
+    assert_eq!(strings("$..book[2].title"), vec!["Moby Dick"]);
+    assert_eq!(strings("$..book[-1].title"), vec!["The Lord of the Rings"]);
+    assert_eq!(strings("$..book[-1:].title"), vec!["The Lord of the Rings"]);
+    assert_eq!(strings("$..book[:2].title"),
+               vec!["Sayings of the Century", "Sword of Honour"]);
+    assert_eq!(strings("$..book[1:3].author"),
+               vec!["Evelyn Waugh", "Herman Melville"]);
+    assert_eq!(strings("$..isbn"), vec!["0-553-21311-3", "0-395-19395-8"]);
+
+    assert_eq!(query(&store, "$.store.*").unwrap().len(), 2);
+    assert_eq!(query(&store, "$.store..price").unwrap().len(), 5);
+    assert_eq!(query(&store, "$..book[*]").unwrap().len(), 4);
+    assert_eq!(query(&store, "$..*").unwrap().len(), 27);
+    assert_eq!(query(&store, "$").unwrap(), vec![&store]);
+    assert!(query(&store, "$.store.book[10]").unwrap().is_empty());
+    assert!(query(&store, "$.store.missing..price").unwrap().is_empty());
+
+    assert_eq!(query(&store, "store"), Err(PathError::MissingRoot));
+    assert_eq!(query(&store, "$.store["), Err(PathError::InvalidSyntax(7)));
+    assert_eq!(query(&store, "$.store..").unwrap_err(),
+               PathError::InvalidSyntax(9));
+    assert_eq!(query(&store, "$.book[x]"), Err(PathError::InvalidSyntax(7)));
+    assert_eq!(query(&store, "$..book[?(@.isbn)]"),
+               Err(PathError::Unsupported(8)));
+    assert_eq!(query(&store, "$..book[0,1]"), Err(PathError::Unsupported(8)));
+    assert_eq!(query(&store, "$..book[::2]"), Err(PathError::Unsupported(8)));
+}
+
+#[test]
+fn test_pointer_join_and_parent() {
+    use serde_json::pointer::{join, parent, parse_pointer};
+
+    let a = join("", "a");
+    assert_eq!(a, "/a");
+    let abc = join(&a, "b/c");
+    assert_eq!(abc, "/a/b~1c");
+    assert_eq!(join(&abc, "~"), "/a/b~1c/~0");
+    assert_eq!(join("", ""), "/");
+    assert_eq!(parse_pointer(&join(&abc, "~1/")).unwrap(), vec!["a", "b/c", "~1/"]);
+
+    assert_eq!(parent(&abc), Some("/a"));
+    assert_eq!(parent("/a"), Some(""));
+    assert_eq!(parent("/a/"), Some("/a"));
+    assert_eq!(parent("/"), Some(""));
+    assert_eq!(parent(""), None);
+    assert_eq!(parent("a/b"), None);
+    assert_eq!(parent("/a~2/b"), None);
+    assert_eq!(parent("/a/b~"), None);
+}
+
 #[test]
 fn test_stack_overflow() {
     let brackets: String = iter::repeat('[').take(127).chain(iter::repeat(']').take(127)).collect();
@@ -1644,3 +3686,178 @@ fn test_stack_overflow() {
         (&brackets, Error::Syntax(ErrorCode::Custom("recursion limit exceeded".into()), 1, 128)),
     ]);
 }
+
+#[test]
+fn test_max_depth() {
+    let overflow = ErrorCode::Custom("recursion limit exceeded".into());
+    let options = ParseOptions::new().max_depth(2);
+    assert!(options.from_str::<Value>("[{}]").is_ok());
+    assert_eq!(options.from_str::<Value>("[{\"a\": []}]").unwrap_err(),
+               Error::Syntax(overflow.clone(), 1, 8));
+    assert_eq!(options.value_from_str("[{\"a\": []}]").unwrap_err(),
+               Error::Syntax(overflow.clone(), 1, 8));
+    assert_eq!(ParseOptions::new().max_depth(0).value_from_str("[]").unwrap_err(),
+               Error::Syntax(overflow.clone(), 1, 1));
+
+    let brackets: String = iter::repeat('[').take(128).collect();
+    assert_eq!(ParseOptions::new().value_from_str(&brackets).unwrap_err(),
+               Error::Syntax(overflow, 1, 128));
+}
+
+#[test]
+fn test_parse_iterative_matches_recursive() {
+    let options = ParseOptions::new();
+    for json in &[
+        "null",
+        " 1.5 ",
+        r#""a""#,
+        "[]",
+        "{}",
+        r#"[1, [2, [3, {}]], {"a": [], "b": {"c": null}}, "x"]"#,
+        r#"{"a": {"b": [true, false]}, "c": [[], [{}]], "a": 2}"#,
+        "\u{feff}[1]",
+    ] {
+        assert_eq!(options.value_from_str(json).unwrap(),
+                   from_str::<Value>(json).unwrap());
+        assert_eq!(options.value_from_slice(json.as_bytes()).unwrap(),
+                   from_str::<Value>(json).unwrap());
+        assert_eq!(options.value_from_reader(json.as_bytes()).unwrap(),
+                   from_str::<Value>(json).unwrap());
+    }
+
+    for json in &[
+        "", "[", "[1", "[1,", "[1,]", "[1 2]", "{", r#"{"a""#, r#"{"a":"#,
+        r#"{"a":1"#, r#"{"a":1,}"#, r#"{"a" 1}"#, "{1:2}", r#"{"a":1 "b":2}"#,
+        "[]]", "[] x", "[nul]", r#"["\x"]"#,
+    ] {
+        assert_eq!(options.value_from_str(json).unwrap_err(),
+                   from_str::<Value>(json).unwrap_err());
+    }
+
+    let limited = ParseOptions::new().max_elements(2).max_string_len(2);
+    for json in &["[[1, 2], 3]", r#"{"a": [1], "b": 2}"#, r#"{"abc": 1}"#,
+                  r#"["abc"]"#] {
+        assert_eq!(limited.value_from_str(json).unwrap_err(),
+                   limited.from_str::<Value>(json).unwrap_err());
+    }
+}
+
+#[test]
+fn test_parse_deeply_nested() {
+    let depth = 100000;
+    let json: String = iter::repeat('[')
+        .take(depth)
+        .chain(iter::once('1'))
+        .chain(iter::repeat(']').take(depth))
+        .collect();
+    let value = ParseOptions::new()
+        .max_depth(usize::MAX)
+        .value_from_str(&json)
+        .unwrap();
+
+    // Take the value apart one level at a time, since dropping it whole
+    // would recurse as deeply as it is nested.
+    let mut levels = 0;
+    let mut value = value;
+    while let Value::Array(mut vec) = value {
+        assert_eq!(vec.len(), 1);
+        value = vec.pop().unwrap();
+        levels += 1;
+    }
+    assert_eq!(levels, depth);
+    assert_eq!(value, Value::U64(1));
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn test_big_number_round_trip() {
+    for json in &[
+        "98765432109876543210",
+        "-98765432109876543210",
+        "-9223372036854775809",
+        "[123456789012345678901234567890,1]",
+    ] {
+        let value: Value = from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&value).unwrap(), *json);
+        assert_eq!(serde_json::to_string(&to_value(&value)).unwrap(), *json);
+    }
+
+    let value: Value = from_str("98765432109876543210").unwrap();
+    assert_eq!(value, Value::BigNumber("98765432109876543210".to_owned()));
+    assert!(value.is_number());
+    assert_eq!(value.as_i128(), Some(98765432109876543210));
+    assert_eq!(value.as_f64(), Some(98765432109876543210.0));
+    assert_eq!(value.as_u64(), None);
+    assert_eq!(from_value::<f64>(value).unwrap(), 98765432109876543210.0);
+
+    // Numbers that fit keep using the existing variants.
+    assert_eq!(from_str::<Value>("18446744073709551615").unwrap(),
+               Value::U64(u64::MAX));
+    assert_eq!(from_str::<Value>("-9223372036854775808").unwrap(),
+               Value::I64(i64::MIN));
+    assert_eq!(from_str::<f64>("98765432109876543210").unwrap(),
+               98765432109876543210.0);
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn test_big_decimal_round_trip() {
+    use serde_json::RawValue;
+
+    for json in &[
+        "0.10000000000000000000001",
+        "-0.10000000000000000000001",
+        "98765432109876543210.5",
+        "1.5e-400",
+        "[3.14159265358979323846264338327950288,1.5]",
+    ] {
+        let value: Value = from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&value).unwrap(), *json);
+        let value: Value = serde_json::from_reader(json.as_bytes()).unwrap();
+        assert_eq!(serde_json::to_string(&value).unwrap(), *json);
+    }
+
+    let value: Value = from_str("0.10000000000000000000001").unwrap();
+    assert_eq!(value, Value::BigNumber("0.10000000000000000000001".to_owned()));
+    assert_eq!(value.as_f64(), Some(0.1));
+    assert_eq!(value.as_i128(), None);
+    assert_eq!(from_str::<f64>("0.10000000000000000000001").unwrap(), 0.1);
+
+    // Decimals an `f64` holds without loss keep using `F64`.
+    for &(json, f) in &[("0.1", 0.1), ("-2.5", -2.5), ("1e300", 1e300), ("1.50", 1.5), ("0.0", 0.0)] {
+        assert_eq!(from_str::<Value>(json).unwrap(), Value::F64(f), "{}", json);
+    }
+
+    // Reading the text of a number does not disturb a `RawValue` around it.
+    let json = "[0.10000000000000000000001, 2]";
+    let raw: RawValue = from_str(json).unwrap();
+    assert_eq!(raw.get(), json);
+    let raw: RawValue = serde_json::from_reader(json.as_bytes()).unwrap();
+    assert_eq!(raw.get(), json);
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn test_big_number_marker_is_private() {
+    // Input cannot pass itself off as a big number.
+    for json in &[
+        r#"{"$serde_json::private::Number":"1,\"x\":2"}"#,
+        r#"{"$serde_json::private::Number":"123"}"#,
+        r#"{"$serde_json::private::Number":5}"#,
+    ] {
+        let value: Value = from_str(json).unwrap();
+        assert!(value.is_object());
+        assert_eq!(serde_json::to_string(&value).unwrap(), *json);
+    }
+
+    // Typed visitors get the nearest `f64` instead of the private map.
+    let big = "98765432109876543210";
+    assert!(from_str::<Map<String, String>>(big).is_err());
+    assert!(from_str::<Map<String, f64>>(big).is_err());
+    let value: Value = from_str(big).unwrap();
+    assert!(from_value::<Map<String, String>>(value.clone()).is_err());
+    assert_eq!(from_value::<f64>(value).unwrap(), 98765432109876543210.0);
+
+    // Text that is not a number is never written out unquoted.
+    assert!(serde_json::to_string(&Value::BigNumber("1,2".to_owned())).is_err());
+}